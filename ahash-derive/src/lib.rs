@@ -0,0 +1,214 @@
+//! A derive macro for [Hash] which is faster with [aHash](https://docs.rs/ahash).
+//!
+//! The standard `#[derive(Hash)]` writes each field to the hasher separately. For a struct with several small
+//! integer fields this results in a call to `write_u8` or `write_u32` per field, and `AHasher` performs a full round
+//! for each of them regardless of how small the value is.
+//!
+//! `#[derive(AHash)]` instead packs runs of adjacent primitive fields into a single `u64` or `u128` and writes that.
+//! Fields of other types are hashed with their own [Hash] implementation, as with the standard derive.
+//!
+//! ```
+//! use ahash_derive::AHash;
+//!
+//! #[derive(AHash, PartialEq, Eq)]
+//! struct Pixel {
+//!     x: u16,
+//!     y: u16,
+//!     rgba: [u8; 4],
+//!     layer: u8,
+//!     visible: bool,
+//! }
+//! ```
+//! Here `x` and `y` are written as one `u64`, then `rgba` is hashed as usual, then `layer` and `visible` are written
+//! as one more `u64`.
+//!
+//! A field can be excluded from the hash with `#[ahash(skip)]`. This is useful for caches or other data which is not
+//! compared by [PartialEq]. Because equal values must have equal hashes, a field should only be skipped if the
+//! [PartialEq] implementation also ignores it.
+//!
+//! Fields are recognised as primitives by how their type is written: either the bare name, such as `u32`, or a path
+//! into the `primitive` module of `core` or `std`, such as `::core::primitive::u32`. Any other path, including a type
+//! alias such as `type Id = u32`, is hashed with its own [Hash] implementation. A user defined type which shadows the
+//! name of a primitive is not supported as the type of a field.
+//!
+//! Note that the generated hash is not the same as the one produced by `#[derive(Hash)]`, so values of types which
+//! derive `AHash` should not be compared with values hashed using the standard derive. (For example via [Borrow])
+//!
+//! [Hash]: core::hash::Hash
+//! [PartialEq]: core::cmp::PartialEq
+//! [Borrow]: core::borrow::Borrow
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{
+    parse_macro_input, parse_quote, Data, DeriveInput, Error, Field, GenericParam, Member, PathArguments, Type,
+};
+
+/// Derives [Hash](core::hash::Hash), packing adjacent primitive fields together. (See the [crate] documentation)
+#[proc_macro_derive(AHash, attributes(ahash))]
+pub fn derive_ahash(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(input).unwrap_or_else(Error::into_compile_error).into()
+}
+
+/// How a field is written to the hasher.
+enum Kind {
+    /// A primitive of at most 64 bits which can be packed with its neighbours.
+    Packed { bits: u32, value: TokenStream2 },
+    /// A 128 bit integer, which is written on its own.
+    Wide(TokenStream2),
+    /// Anything else, which is hashed using its `Hash` implementation.
+    Other(TokenStream2),
+}
+
+fn expand(mut input: DeriveInput) -> Result<TokenStream2, Error> {
+    let fields = match &input.data {
+        Data::Struct(data) => &data.fields,
+        Data::Enum(data) => {
+            return Err(Error::new(data.enum_token.span, "AHash can only be derived for structs"));
+        }
+        Data::Union(data) => {
+            return Err(Error::new(data.union_token.span, "AHash can only be derived for structs"));
+        }
+    };
+    let mut kinds = Vec::new();
+    for (field, member) in fields.iter().zip(fields.members()) {
+        if !is_skipped(field)? {
+            kinds.push(classify(&field.ty, member));
+        }
+    }
+    let body = write_fields(kinds);
+
+    for param in input.generics.params.iter_mut() {
+        if let GenericParam::Type(param) = param {
+            param.bounds.push(parse_quote!(::core::hash::Hash));
+        }
+    }
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::core::hash::Hash for #name #ty_generics #where_clause {
+            #[inline]
+            fn hash<__H: ::core::hash::Hasher>(&self, state: &mut __H) {
+                #body
+            }
+        }
+    })
+}
+
+fn is_skipped(field: &Field) -> Result<bool, Error> {
+    let mut skip = false;
+    for attr in field.attrs.iter().filter(|attr| attr.path().is_ident("ahash")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("skip") {
+                skip = true;
+                Ok(())
+            } else {
+                Err(meta.error("unsupported ahash attribute, expected `skip`"))
+            }
+        })?;
+    }
+    Ok(skip)
+}
+
+fn classify(ty: &Type, member: Member) -> Kind {
+    let field = quote!(self.#member);
+    let name = match primitive_name(ty) {
+        Some(name) => name,
+        None => return Kind::Other(field),
+    };
+    // Signed values are converted to unsigned ones of the same width so they are not sign extended.
+    let (bits, value) = match name.as_str() {
+        "u8" | "u16" | "u32" | "u64" | "bool" => (width(&name), quote!(::core::primitive::u128::from(#field))),
+        "char" => (32, quote!(::core::primitive::u128::from(#field))),
+        "i8" => (8, quote!(::core::primitive::u128::from(#field as u8))),
+        "i16" => (16, quote!(::core::primitive::u128::from(#field as u16))),
+        "i32" => (32, quote!(::core::primitive::u128::from(#field as u32))),
+        "i64" => (64, quote!(::core::primitive::u128::from(#field as u64))),
+        "usize" | "isize" => (64, quote!(::core::primitive::u128::from(#field as u64))),
+        "u128" => return Kind::Wide(field),
+        "i128" => return Kind::Wide(quote!(#field as u128)),
+        _ => return Kind::Other(field),
+    };
+    Kind::Packed { bits, value }
+}
+
+/// Returns the name of the type if it is written as a primitive could be: either a bare name with no generics, or a
+/// path into `core::primitive` or `std::primitive`. (Whether the name is actually that of a primitive is left to the
+/// caller)
+fn primitive_name(ty: &Type) -> Option<String> {
+    let path = match ty {
+        Type::Path(path) if path.qself.is_none() => &path.path,
+        _ => return None,
+    };
+    if path.segments.iter().any(|segment| !matches!(segment.arguments, PathArguments::None)) {
+        return None;
+    }
+    let names: Vec<String> = path.segments.iter().map(|segment| segment.ident.to_string()).collect();
+    match names.as_slice() {
+        [name] if path.leading_colon.is_none() => Some(name.clone()),
+        [krate, module, name] if (krate == "core" || krate == "std") && module == "primitive" => Some(name.clone()),
+        _ => None,
+    }
+}
+
+fn width(name: &str) -> u32 {
+    match name {
+        "bool" | "u8" => 8,
+        "u16" => 16,
+        "u32" => 32,
+        _ => 64,
+    }
+}
+
+/// Generates the body of `hash`, grouping runs of packed fields into words of at most 128 bits.
+fn write_fields(kinds: Vec<Kind>) -> TokenStream2 {
+    let mut body = TokenStream2::new();
+    let mut word: Vec<(u32, u32, TokenStream2)> = Vec::new();
+    let mut used = 0;
+    for kind in kinds {
+        match kind {
+            Kind::Packed { bits, value } => {
+                if used + bits > 128 {
+                    body.extend(flush(&mut word));
+                    used = 0;
+                }
+                word.push((used, bits, value));
+                used += bits;
+            }
+            Kind::Wide(value) => {
+                body.extend(flush(&mut word));
+                used = 0;
+                body.extend(quote!(::core::hash::Hasher::write_u128(state, #value);));
+            }
+            Kind::Other(value) => {
+                body.extend(flush(&mut word));
+                used = 0;
+                body.extend(quote!(::core::hash::Hash::hash(&#value, state);));
+            }
+        }
+    }
+    body.extend(flush(&mut word));
+    body
+}
+
+/// Writes the packed fields in `word`, each of which is a shift, a width in bits and a `u128` value.
+fn flush(word: &mut Vec<(u32, u32, TokenStream2)>) -> TokenStream2 {
+    let used = match word.last() {
+        Some((shift, bits, _)) => shift + bits,
+        None => return TokenStream2::new(),
+    };
+    let parts = word.drain(..).map(|(shift, _, value)| {
+        if shift == 0 {
+            value
+        } else {
+            quote!((#value << #shift))
+        }
+    });
+    let packed = quote!(#(#parts)|*);
+    if used <= 64 {
+        quote!(::core::hash::Hasher::write_u64(state, (#packed) as u64);)
+    } else {
+        quote!(::core::hash::Hasher::write_u128(state, #packed);)
+    }
+}
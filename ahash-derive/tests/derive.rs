@@ -0,0 +1,164 @@
+use ahash::RandomState;
+use ahash_derive::AHash;
+use std::collections::HashSet;
+use std::hash::{Hash, Hasher};
+
+#[derive(AHash, Clone, PartialEq, Eq, Debug)]
+struct Small {
+    a: u8,
+    b: i16,
+    c: u32,
+    d: bool,
+    e: char,
+    f: i64,
+}
+
+#[derive(AHash, Clone, PartialEq, Eq, Debug)]
+struct Mixed {
+    id: u64,
+    name: String,
+    wide: i128,
+    flags: u8,
+    size: usize,
+}
+
+#[derive(AHash, Clone, Debug)]
+struct Cached {
+    key: u32,
+    tag: u16,
+    #[ahash(skip)]
+    hash_cache: Option<u64>,
+}
+
+impl PartialEq for Cached {
+    fn eq(&self, other: &Self) -> bool {
+        self.key == other.key && self.tag == other.tag
+    }
+}
+
+impl Eq for Cached {}
+
+#[derive(AHash, PartialEq, Eq)]
+struct Tuple(u16, u16, Vec<u8>);
+
+#[derive(AHash, PartialEq, Eq)]
+struct Generic<T> {
+    count: u32,
+    value: T,
+}
+
+#[derive(AHash)]
+struct Unit;
+
+type Id = u32;
+
+#[allow(unused_qualifications)]
+#[derive(AHash)]
+struct Paths {
+    a: ::core::primitive::u16,
+    b: std::primitive::u8,
+    c: Id,
+}
+
+/// Records the calls made to the hasher.
+#[derive(Default)]
+struct Recorder(Vec<String>);
+
+impl Hasher for Recorder {
+    fn finish(&self) -> u64 {
+        0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        self.0.push(format!("bytes {:?}", bytes));
+    }
+
+    fn write_u64(&mut self, i: u64) {
+        self.0.push(format!("u64 {:x}", i));
+    }
+
+    fn write_u128(&mut self, i: u128) {
+        self.0.push(format!("u128 {:x}", i));
+    }
+
+    fn write_usize(&mut self, i: usize) {
+        self.0.push(format!("usize {:x}", i));
+    }
+}
+
+fn calls<T: Hash>(value: &T) -> Vec<String> {
+    let mut recorder = Recorder::default();
+    value.hash(&mut recorder);
+    recorder.0
+}
+
+fn small(i: u64) -> Small {
+    Small {
+        a: i as u8,
+        b: -(i as i16),
+        c: (i * 7) as u32,
+        d: i & 1 == 0,
+        e: char::from_u32(0x41 + (i % 26) as u32).unwrap(),
+        f: -(i as i64) * 1000,
+    }
+}
+
+#[test]
+fn test_packs_fields() {
+    let value = Small { a: 1, b: -1, c: 2, d: true, e: 'A', f: -1 };
+    // a, b, c, d and e fit in 88 bits, then f starts a new word.
+    assert_eq!(
+        calls(&value),
+        vec!["u128 410100000002ffff01".to_string(), "u64 ffffffffffffffff".to_string()]
+    );
+    assert_eq!(
+        calls(&Tuple(1, 2, vec![9])),
+        vec!["u64 20001".to_string(), "usize 1".to_string(), "bytes [9]".to_string()]
+    );
+    assert_eq!(calls(&Generic { count: 3, value: 5_u128 }), vec!["u64 3".to_string(), "u128 5".to_string()]);
+    assert!(calls(&Unit).is_empty());
+}
+
+#[test]
+fn test_only_packs_primitive_paths() {
+    // `a` and `b` are packed, but the alias `Id` is hashed by the `Hash` impl of `u32`, which calls `write`.
+    assert_eq!(
+        calls(&Paths { a: 1, b: 2, c: 3 }),
+        vec!["u64 20001".to_string(), "bytes [3, 0, 0, 0]".to_string()]
+    );
+}
+
+#[test]
+fn test_respects_eq() {
+    let state = RandomState::with_seeds(1, 2, 3, 4);
+    for i in 0..100 {
+        assert_eq!(state.hash_one(small(i)), state.hash_one(small(i).clone()));
+    }
+    let hashes: HashSet<u64> = (0..1000).map(|i| state.hash_one(small(i))).collect();
+    assert_eq!(hashes.len(), 1000);
+
+    let mixed = Mixed { id: 1, name: "one".to_string(), wide: -1, flags: 3, size: 4 };
+    assert_eq!(state.hash_one(&mixed), state.hash_one(mixed.clone()));
+    let renamed = Mixed { name: "two".to_string(), ..mixed.clone() };
+    assert_ne!(state.hash_one(&mixed), state.hash_one(&renamed));
+    let resized = Mixed { size: 5, ..mixed.clone() };
+    assert_ne!(state.hash_one(&mixed), state.hash_one(&resized));
+}
+
+#[test]
+fn test_skip() {
+    let state = RandomState::with_seeds(1, 2, 3, 4);
+    let empty = Cached { key: 7, tag: 1, hash_cache: None };
+    let filled = Cached { hash_cache: Some(12345), ..empty.clone() };
+    assert_eq!(empty, filled);
+    assert_ne!(empty.hash_cache, filled.hash_cache);
+    assert_eq!(state.hash_one(&empty), state.hash_one(&filled));
+    assert_eq!(calls(&filled), vec!["u64 100000007".to_string()]);
+    let other = Cached { tag: 2, ..empty.clone() };
+    assert_ne!(state.hash_one(&empty), state.hash_one(&other));
+
+    let mut set = HashSet::with_hasher(state.clone());
+    set.insert(empty);
+    assert!(set.contains(&filled));
+    assert!(!set.contains(&other));
+}
@@ -1,5 +1,6 @@
 
 use crate::convert::*;
+use crate::hasher_state::{self, ImportStateError, STATE_LEN};
 use crate::operations::*;
 use crate::random_state::PI;
 use crate::RandomState;
@@ -33,22 +34,9 @@ impl AHasher {
     /// This method directly creates the hasher instance and performs no transformation on the provided seeds. This may
     /// be useful where a HashBuilder is not desired, such as for testing purposes.
     ///
-    /// # Example
-    ///
-    /// ```
-    /// use std::hash::Hasher;
-    /// use ahash::AHasher;
-    ///
-    /// let mut hasher = AHasher::new_with_keys(1234, 5678);
-    ///
-    /// hasher.write_u32(1989);
-    /// hasher.write_u8(11);
-    /// hasher.write_u8(9);
-    /// hasher.write(b"Huh?");
-    ///
-    /// println!("Hash is {:x}!", hasher.finish());
-    /// ```
+    /// Outside of the crate a hasher with chosen seeds is created by `RandomState::with_seeds(..).build_hasher()`.
     #[inline]
+    #[allow(dead_code)] // Only used by tests.
     pub(crate) fn new_with_keys(key1: u128, key2: u128) -> Self {
         let pi: [u128; 2] = PI.convert();
         let key1 = key1 ^ pi[0];
@@ -81,76 +69,52 @@ impl AHasher {
     }
 
     #[inline(always)]
-    fn hash_in(&mut self, new_value: u128) {
-        self.enc = aesenc(self.enc, new_value);
+    fn hash_in<A: AesRound>(&mut self, new_value: u128) {
+        self.enc = A::aesenc(self.enc, new_value);
         self.sum = shuffle_and_add(self.sum, new_value);
     }
 
     #[inline(always)]
-    fn hash_in_2(&mut self, v1: u128, v2: u128) {
-        self.enc = aesenc(self.enc, v1);
+    fn hash_in_2<A: AesRound>(&mut self, v1: u128, v2: u128) {
+        self.enc = A::aesenc(self.enc, v1);
         self.sum = shuffle_and_add(self.sum, v1);
-        self.enc = aesenc(self.enc, v2);
+        self.enc = A::aesenc(self.enc, v2);
         self.sum = shuffle_and_add(self.sum, v2);
     }
 
+    /// Returns a 128 bit hash of the values written so far.
+    ///
+    /// This is useful where 64 bits would produce too many collisions, such as for fingerprinting content or for
+    /// double hashing. The lower 64 bits are the same as those returned by `finish()`.
     #[inline]
-    #[cfg(feature = "specialize")]
-    fn short_finish(&self) -> u64 {
-        let combined = aesdec(self.sum, self.enc);
-        let result: [u64; 2] = aesenc(combined, combined).convert();
-        result[0]
-    }
-}
-
-/// Provides [Hasher] methods to hash all of the primitive types.
-///
-/// [Hasher]: core::hash::Hasher
-impl Hasher for AHasher {
-    #[inline]
-    fn write_u8(&mut self, i: u8) {
-        self.write_u64(i as u64);
-    }
-
-    #[inline]
-    fn write_u16(&mut self, i: u16) {
-        self.write_u64(i as u64);
-    }
-
-    #[inline]
-    fn write_u32(&mut self, i: u32) {
-        self.write_u64(i as u64);
-    }
-
-    #[inline]
-    fn write_u128(&mut self, i: u128) {
-        self.hash_in(i);
+    pub fn finish_u128(&self) -> u128 {
+        self.finish_u128_with::<NativeAes>()
     }
 
+    /// [AHasher::finish_u128] computed with the round `A`.
     #[inline]
-    #[cfg(any(
-        target_pointer_width = "64",
-        target_pointer_width = "32",
-        target_pointer_width = "16"
-    ))]
-    fn write_usize(&mut self, i: usize) {
-        self.write_u64(i as u64);
+    pub(crate) fn finish_u128_with<A: AesRound>(&self) -> u128 {
+        let combined = A::aesdec(self.sum, self.enc);
+        A::aesenc(A::aesenc(combined, self.key), combined)
     }
 
+    /// [Hasher::finish] computed with the round `A`.
     #[inline]
-    #[cfg(target_pointer_width = "128")]
-    fn write_usize(&mut self, i: usize) {
-        self.write_u128(i as u128);
+    pub(crate) fn finish_with<A: AesRound>(&self) -> u64 {
+        let result: [u64; 2] = self.finish_u128_with::<A>().convert();
+        result[0]
     }
 
+    /// [Hasher::write_u128] computed with the round `A`.
     #[inline]
-    fn write_u64(&mut self, i: u64) {
-        self.write_u128(i as u128);
+    pub(crate) fn write_u128_with<A: AesRound>(&mut self, i: u128) {
+        self.hash_in::<A>(i);
     }
 
+    /// [Hasher::write] computed with the round `A`.
     #[inline]
     #[allow(clippy::collapsible_if)]
-    fn write(&mut self, input: &[u8]) {
+    pub(crate) fn write_with<A: AesRound>(&mut self, input: &[u8]) {
         let mut data = input;
         let length = data.len();
         add_in_length(&mut self.enc, length as u64);
@@ -158,16 +122,16 @@ impl Hasher for AHasher {
         //A 'binary search' on sizes reduces the number of comparisons.
         if data.len() <= 8 {
             let value = read_small(data);
-            self.hash_in(value.convert());
+            self.hash_in::<A>(value.convert());
         } else {
             if data.len() > 32 {
                 if data.len() > 64 {
                     let tail = data.read_last_u128x4();
                     let mut current: [u128; 4] = [self.key; 4];
-                    current[0] = aesenc(current[0], tail[0]);
-                    current[1] = aesenc(current[1], tail[1]);
-                    current[2] = aesenc(current[2], tail[2]);
-                    current[3] = aesenc(current[3], tail[3]);
+                    current[0] = A::aesenc(current[0], tail[0]);
+                    current[1] = A::aesenc(current[1], tail[1]);
+                    current[2] = A::aesenc(current[2], tail[2]);
+                    current[3] = A::aesenc(current[3], tail[3]);
                     let mut sum: [u128; 2] = [self.key, self.key];
                     sum[0] = add_by_64s(sum[0].convert(), tail[0].convert()).convert();
                     sum[1] = add_by_64s(sum[1].convert(), tail[1].convert()).convert();
@@ -175,53 +139,148 @@ impl Hasher for AHasher {
                     sum[1] = shuffle_and_add(sum[1], tail[3]);
                     while data.len() > 64 {
                         let (blocks, rest) = data.read_u128x4();
-                        current[0] = aesenc(current[0], blocks[0]);
-                        current[1] = aesenc(current[1], blocks[1]);
-                        current[2] = aesenc(current[2], blocks[2]);
-                        current[3] = aesenc(current[3], blocks[3]);
+                        current[0] = A::aesenc(current[0], blocks[0]);
+                        current[1] = A::aesenc(current[1], blocks[1]);
+                        current[2] = A::aesenc(current[2], blocks[2]);
+                        current[3] = A::aesenc(current[3], blocks[3]);
                         sum[0] = shuffle_and_add(sum[0], blocks[0]);
                         sum[1] = shuffle_and_add(sum[1], blocks[1]);
                         sum[0] = shuffle_and_add(sum[0], blocks[2]);
                         sum[1] = shuffle_and_add(sum[1], blocks[3]);
                         data = rest;
                     }
-                    self.hash_in_2(aesenc(current[0], current[1]), aesenc(current[2], current[3]));
-                    self.hash_in(add_by_64s(sum[0].convert(), sum[1].convert()).convert());
+                    self.hash_in_2::<A>(A::aesenc(current[0], current[1]), A::aesenc(current[2], current[3]));
+                    self.hash_in::<A>(add_by_64s(sum[0].convert(), sum[1].convert()).convert());
                 } else {
                     //len 33-64
                     let (head, _) = data.read_u128x2();
                     let tail = data.read_last_u128x2();
-                    self.hash_in_2(head[0], head[1]);
-                    self.hash_in_2(tail[0], tail[1]);
+                    self.hash_in_2::<A>(head[0], head[1]);
+                    self.hash_in_2::<A>(tail[0], tail[1]);
                 }
             } else {
                 if data.len() > 16 {
                     //len 17-32
-                    self.hash_in_2(data.read_u128().0, data.read_last_u128());
+                    self.hash_in_2::<A>(data.read_u128().0, data.read_last_u128());
                 } else {
                     //len 9-16
                     let value: [u64; 2] = [data.read_u64().0, data.read_last_u64()];
-                    self.hash_in(value.convert());
+                    self.hash_in::<A>(value.convert());
                 }
             }
         }
     }
+
+    /// Saves the current state of the hasher, so that it can be resumed later with [AHasher::import_state].
+    ///
+    /// This allows a long input to be hashed in parts, possibly in different processes. The state can only be
+    /// imported by a build of the same version of aHash which uses the same algorithm. (An [AHasher] resumed in this
+    /// way produces the same result as the original would have)
+    ///
+    /// The state contains the keys of the hasher, so it should be kept secret if DOS resistance is desired.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::hash::{BuildHasher, Hasher};
+    /// use ahash::{AHasher, RandomState};
+    ///
+    /// let mut hasher = RandomState::with_seeds(1, 2, 3, 4).build_hasher();
+    /// hasher.write(b"The first part");
+    /// let saved = hasher.export_state();
+    ///
+    /// let mut resumed = AHasher::import_state(&saved).unwrap();
+    /// hasher.write(b"The second part");
+    /// resumed.write(b"The second part");
+    /// assert_eq!(hasher.finish(), resumed.finish());
+    /// ```
     #[inline]
-    fn finish(&self) -> u64 {
+    pub fn export_state(&self) -> [u8; STATE_LEN] {
+        hasher_state::encode(hasher_state::AES, [self.enc, self.sum, self.key])
+    }
+
+    /// Recreates a hasher from a state returned by [AHasher::export_state].
+    ///
+    /// This fails if the state is malformed, or if it was exported by a different algorithm than the one this hasher
+    /// uses. (Such as on a CPU without AES instructions)
+    #[inline]
+    pub fn import_state(state: &[u8]) -> Result<AHasher, ImportStateError> {
+        let [enc, sum, key] = hasher_state::decode(hasher_state::AES, state)?;
+        Ok(AHasher { enc, sum, key })
+    }
+
+    #[inline]
+    #[allow(dead_code)] // Depends on the backend
+    fn short_finish(&self) -> u64 {
         let combined = aesdec(self.sum, self.enc);
-        let result: [u64; 2] = aesenc(aesenc(combined, self.key), combined).convert();
+        let result: [u64; 2] = aesenc(combined, combined).convert();
         result[0]
     }
 }
 
-#[cfg(feature = "specialize")]
+/// Provides [Hasher] methods to hash all of the primitive types.
+///
+/// [Hasher]: core::hash::Hasher
+impl Hasher for AHasher {
+    #[inline]
+    fn write_u8(&mut self, i: u8) {
+        self.write_u64(i as u64);
+    }
+
+    #[inline]
+    fn write_u16(&mut self, i: u16) {
+        self.write_u64(i as u64);
+    }
+
+    #[inline]
+    fn write_u32(&mut self, i: u32) {
+        self.write_u64(i as u64);
+    }
+
+    #[inline]
+    fn write_u128(&mut self, i: u128) {
+        self.write_u128_with::<NativeAes>(i);
+    }
+
+    #[inline]
+    #[cfg(any(
+        target_pointer_width = "64",
+        target_pointer_width = "32",
+        target_pointer_width = "16"
+    ))]
+    fn write_usize(&mut self, i: usize) {
+        self.write_u64(i as u64);
+    }
+
+    #[inline]
+    #[cfg(target_pointer_width = "128")]
+    fn write_usize(&mut self, i: usize) {
+        self.write_u128(i as u128);
+    }
+
+    #[inline]
+    fn write_u64(&mut self, i: u64) {
+        self.write_u128(i as u128);
+    }
+
+    #[inline]
+    fn write(&mut self, input: &[u8]) {
+        self.write_with::<NativeAes>(input);
+    }
+
+    #[inline]
+    fn finish(&self) -> u64 {
+        self.finish_with::<NativeAes>()
+    }
+}
+
+#[allow(dead_code)] // Depends on the backend
 pub(crate) struct AHasherU64 {
     pub(crate) buffer: u64,
     pub(crate) pad: u64,
 }
 
 /// A specialized hasher for only primitives under 64 bits.
-#[cfg(feature = "specialize")]
 impl Hasher for AHasherU64 {
     #[inline]
     fn finish(&self) -> u64 {
@@ -265,11 +324,34 @@ impl Hasher for AHasherU64 {
     }
 }
 
-#[cfg(feature = "specialize")]
+impl HashX4<u64> for AHasherU64 {
+    /// Hashes four values in lockstep, with the same results as writing each of them to a copy of this hasher.
+    #[inline(always)]
+    fn hash_x4(&self, values: [&u64; 4]) -> [u64; 4] {
+        let lane = |value: u64| {
+            let mut hasher = AHasherU64 {
+                buffer: self.buffer,
+                pad: self.pad,
+            };
+            hasher.write_u64(value);
+            hasher.finish()
+        };
+        [lane(*values[0]), lane(*values[1]), lane(*values[2]), lane(*values[3])]
+    }
+}
+
+impl HashX4<u32> for AHasherU64 {
+    #[inline(always)]
+    fn hash_x4(&self, values: [&u32; 4]) -> [u64; 4] {
+        let values = [*values[0] as u64, *values[1] as u64, *values[2] as u64, *values[3] as u64];
+        self.hash_x4([&values[0], &values[1], &values[2], &values[3]])
+    }
+}
+
+#[allow(dead_code)] // Depends on the backend
 pub(crate) struct AHasherFixed(pub AHasher);
 
 /// A specialized hasher for fixed size primitives larger than 64 bits.
-#[cfg(feature = "specialize")]
 impl Hasher for AHasherFixed {
     #[inline]
     fn finish(&self) -> u64 {
@@ -312,12 +394,24 @@ impl Hasher for AHasherFixed {
     }
 }
 
-#[cfg(feature = "specialize")]
+impl HashX4<u128> for AHasherFixed {
+    /// Hashes four values in lockstep, with the same results as writing each of them to a copy of this hasher.
+    #[inline(always)]
+    fn hash_x4(&self, values: [&u128; 4]) -> [u64; 4] {
+        let lane = |value: u128| {
+            let mut hasher = AHasherFixed(self.0.clone());
+            hasher.write_u128(value);
+            hasher.finish()
+        };
+        [lane(*values[0]), lane(*values[1]), lane(*values[2]), lane(*values[3])]
+    }
+}
+
+#[allow(dead_code)] // Depends on the backend
 pub(crate) struct AHasherStr(pub AHasher);
 
 /// A specialized hasher for strings
 /// Note that the other types don't panic because the hash impl for String tacks on an unneeded call. (As does vec)
-#[cfg(feature = "specialize")]
 impl Hasher for AHasherStr {
     #[inline]
     fn finish(&self) -> u64 {
@@ -332,12 +426,7 @@ impl Hasher for AHasherStr {
             self.0.enc = aesdec(self.0.sum, self.0.enc);
             self.0.enc = aesenc(aesenc(self.0.enc, self.0.key), self.0.enc);
         } else {
-            add_in_length(&mut self.0.enc, bytes.len() as u64);
-
-            let value = read_small(bytes).convert();
-            self.0.sum = shuffle_and_add(self.0.sum, value);
-            self.0.enc = aesdec(self.0.sum, self.0.enc);
-            self.0.enc = aesenc(aesenc(self.0.enc, self.0.key), self.0.enc);
+            self.write_short(bytes);
         }
     }
 
@@ -360,16 +449,60 @@ impl Hasher for AHasherStr {
     fn write_usize(&mut self, _i: usize) {}
 }
 
+impl AHasherStr {
+    #[inline(always)]
+    #[allow(dead_code)] // Depends on the backend
+    fn write_short(&mut self, bytes: &[u8]) {
+        add_in_length(&mut self.0.enc, bytes.len() as u64);
+
+        let value = read_small(bytes).convert();
+        self.0.sum = shuffle_and_add(self.0.sum, value);
+        self.0.enc = aesdec(self.0.sum, self.0.enc);
+        self.0.enc = aesenc(aesenc(self.0.enc, self.0.key), self.0.enc);
+    }
+
+    /// Kept out of line so that `hash_x4` is small enough to inline into the caller's loop.
+    #[inline(never)]
+    #[allow(dead_code)] // Depends on the backend
+    fn hash_long_x4(&self, values: [&[u8]; 4]) -> [u64; 4] {
+        let lane = |value: &[u8]| {
+            let mut hasher = AHasherStr(self.0.clone());
+            hasher.write(value);
+            hasher.finish()
+        };
+        [lane(values[0]), lane(values[1]), lane(values[2]), lane(values[3])]
+    }
+}
+
+impl<B: AsRef<[u8]>> HashX4<B> for AHasherStr {
+    /// Hashes four values in lockstep, with the same results as writing each of them to a copy of this hasher.
+    /// (Checking the lengths once for the whole group, so that short inputs take the same path in every lane)
+    #[inline(always)]
+    fn hash_x4(&self, values: [&B; 4]) -> [u64; 4] {
+        let values = [values[0].as_ref(), values[1].as_ref(), values[2].as_ref(), values[3].as_ref()];
+        if values.iter().all(|value| value.len() <= 8) {
+            let lane = |value: &[u8]| {
+                let mut hasher = AHasherStr(self.0.clone());
+                hasher.write_short(value);
+                hasher.finish()
+            };
+            [lane(values[0]), lane(values[1]), lane(values[2]), lane(values[3])]
+        } else {
+            self.hash_long_x4(values)
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::convert::Convert;
     use crate::operations::aesenc;
     use crate::RandomState;
-    use std::hash::{BuildHasher, Hasher};
+    use std::hash::Hasher;
     #[test]
     fn test_sanity() {
-        let mut hasher = RandomState::with_seeds(1, 2, 3, 4).build_hasher();
+        let mut hasher = AHasher::from_random_state(&RandomState::with_seeds(1, 2, 3, 4));
         hasher.write_u64(0);
         let h1 = hasher.finish();
         hasher.write(&[1, 0, 0, 0, 0, 0, 0, 0]);
@@ -431,4 +564,26 @@ mod tests {
         let bytes: u64 = as_array!(input, 8).convert();
         assert_eq!(bytes, 0x6464646464646464);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_export_import_state() {
+        let mut hasher = AHasher::new_with_keys(1234, 5678);
+        hasher.write(b"first");
+        hasher.write_u64(42);
+        let state = hasher.export_state();
+        let mut resumed = AHasher::import_state(&state).unwrap();
+        assert_eq!(hasher.finish_u128(), resumed.finish_u128());
+        hasher.write(b"second");
+        resumed.write(b"second");
+        assert_eq!(hasher.finish(), resumed.finish());
+        assert_eq!(state, AHasher::import_state(&state).unwrap().export_state());
+    }
+
+    #[test]
+    fn test_import_rejects_other_backend() {
+        use crate::hasher_state::ImportStateError;
+
+        let fallback_state = crate::fallback_hash::AHasher::new_with_keys(1, 2).export_state();
+        assert_eq!(Some(ImportStateError::BackendMismatch), AHasher::import_state(&fallback_state).err());
+    }
+}
@@ -0,0 +1,117 @@
+//! [BuildHasher]s which always use a particular algorithm. (See [AesHasher](crate::AesHasher) and
+//! [FallbackHasher](crate::FallbackHasher))
+use crate::RandomState;
+use core::fmt;
+use core::hash::BuildHasher;
+
+macro_rules! backend_random_state {
+    ($(#[$attr:meta])* $name:ident, $hasher:ty) => {
+        $(#[$attr])*
+        #[derive(Clone)]
+        pub struct $name {
+            state: RandomState,
+        }
+
+        impl $name {
+            /// Creates a new state using random keys. (See [RandomState::new])
+            #[cfg(any(feature = "compile-time-rng", feature = "runtime-rng", feature = "no-rng"))]
+            #[inline]
+            pub fn new() -> $name {
+                $name::with_state(RandomState::new())
+            }
+
+            /// Creates a state which uses the keys of the provided [RandomState].
+            #[inline]
+            pub fn with_state(state: RandomState) -> $name {
+                $name { state }
+            }
+
+            /// Returns the [RandomState] that provides the keys.
+            #[inline]
+            pub fn state(&self) -> &RandomState {
+                &self.state
+            }
+        }
+
+        impl fmt::Debug for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.pad(concat!(stringify!($name), " { .. }"))
+            }
+        }
+
+        /// Creates a state with random keys. This is the same as `new()`.
+        #[cfg(any(feature = "compile-time-rng", feature = "runtime-rng", feature = "no-rng"))]
+        impl Default for $name {
+            #[inline]
+            fn default() -> Self {
+                Self::new()
+            }
+        }
+
+        impl From<RandomState> for $name {
+            #[inline]
+            fn from(state: RandomState) -> Self {
+                Self::with_state(state)
+            }
+        }
+
+        impl BuildHasher for $name {
+            type Hasher = $hasher;
+
+            #[inline]
+            fn build_hasher(&self) -> $hasher {
+                <$hasher>::from_random_state(&self.state)
+            }
+        }
+    };
+}
+
+backend_random_state!(
+    /// A [BuildHasher] which creates [FallbackHasher](crate::FallbackHasher)s, regardless of which algorithm
+    /// [AHasher](crate::AHasher) uses.
+    FallbackRandomState,
+    crate::FallbackHasher
+);
+
+backend_random_state!(
+    /// A [BuildHasher] which creates [AesHasher](crate::AesHasher)s, regardless of which algorithm
+    /// [AHasher](crate::AHasher) uses.
+    AesRandomState,
+    crate::AesHasher
+);
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{config, Backend};
+    use core::hash::Hasher;
+
+    fn hash_with<B: BuildHasher>(build: &B, data: &[u8]) -> u64 {
+        let mut hasher = build.build_hasher();
+        hasher.write(data);
+        hasher.write_u64(1234);
+        hasher.finish()
+    }
+
+    #[test]
+    fn test_matches_random_state() {
+        let state = RandomState::with_seeds(1, 2, 3, 4);
+        let data = b"a string of some length";
+        let expected = hash_with(&state, data);
+        let fallback = hash_with(&FallbackRandomState::with_state(state.clone()), data);
+        assert_eq!(fallback == expected, config().backend == Backend::Fallback);
+        let aes = hash_with(&AesRandomState::from(state.clone()), data);
+        assert_eq!(aes == expected, config().backend == Backend::Aes);
+        assert_ne!(aes, fallback);
+    }
+
+    #[test]
+    fn test_same_keys() {
+        let state = FallbackRandomState::with_state(RandomState::with_seeds(1, 2, 3, 4));
+        assert_eq!(hash_with(&state, b"abc"), hash_with(&state.clone(), b"abc"));
+        assert_ne!(
+            hash_with(&state, b"abc"),
+            hash_with(&FallbackRandomState::with_state(RandomState::with_seeds(5, 2, 3, 4)), b"abc")
+        );
+    }
+}
@@ -0,0 +1,218 @@
+//! A description of the algorithm and seeds in use. (See [config])
+use core::fmt;
+
+/// The algorithm used by [AHasher](crate::AHasher).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Backend {
+    /// The algorithm based on AES instructions.
+    Aes,
+    /// The algorithm used when AES instructions are not available, based on multiplication.
+    Fallback,
+}
+
+/// Where the process wide seeds, which the keys of every [RandomState](crate::RandomState) are derived from, come from.
+/// (Until [reseed_after_fork](crate::random_state::reseed_after_fork) is called, see [Config::reseeded_after_fork])
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum SeedSource {
+    /// Random numbers obtained from the operating system when the program runs. (The `runtime-rng` feature)
+    Runtime,
+    /// Random numbers generated when the binary was compiled. (The `compile-time-rng` feature)
+    CompileTime,
+    /// Constants, because neither `runtime-rng` nor `compile-time-rng` are enabled.
+    Fixed,
+    /// Derived from the `AHASH_DETERMINISTIC_SEED` environment variable. (See [testing](crate::testing))
+    Deterministic,
+    /// The operating system failed to provide random numbers, so the seeds were provided by the
+    /// [SeedFailurePolicy](crate::random_state::SeedFailurePolicy).
+    FailurePolicy,
+    /// The seeds have not been obtained yet, because no [RandomState](crate::RandomState) has been created. ([config]
+    /// does not obtain them itself)
+    NotSeeded,
+}
+
+/// A description of how aHash is configured in this binary. (See [config])
+///
+/// This implements [Display](fmt::Display), so it can be logged when a program starts.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Config {
+    /// The algorithm used by [AHasher](crate::AHasher).
+    pub backend: Backend,
+    /// True if the backend was chosen when the program started rather than when it was compiled. (The
+    /// `runtime-dispatch` feature)
+    pub runtime_dispatch: bool,
+    /// True if hashing of primitives and strings is specialized. (This is only available on nightly)
+    pub specialize: bool,
+    /// True if the fallback algorithm uses a 128 bit multiply. (This is decided by the target platform)
+    pub folded_multiply: bool,
+    /// Where the process wide seeds come from.
+    pub seed_source: SeedSource,
+    /// True if [reseed_after_fork](crate::random_state::reseed_after_fork) has been called, (or with the `fork-safe`
+    /// feature, has been done automatically) so that the keys of new [RandomState](crate::RandomState)s are derived
+    /// from seeds obtained from the operating system at that point instead of the process wide seeds.
+    pub reseeded_after_fork: bool,
+    /// True if a source of randomness was installed with
+    /// [set_random_source](crate::random_state::set_random_source).
+    pub custom_random_source: bool,
+    /// The Cargo features of aHash which are enabled.
+    pub features: &'static [&'static str],
+}
+
+const FEATURES: &[&str] = &[
+    #[cfg(feature = "std")]
+    "std",
+    #[cfg(feature = "runtime-rng")]
+    "runtime-rng",
+    #[cfg(feature = "compile-time-rng")]
+    "compile-time-rng",
+    #[cfg(feature = "no-rng")]
+    "no-rng",
+    #[cfg(feature = "runtime-dispatch")]
+    "runtime-dispatch",
+    #[cfg(feature = "force-fallback")]
+    "force-fallback",
+    #[cfg(feature = "serde")]
+    "serde",
+    #[cfg(feature = "digest")]
+    "digest",
+    #[cfg(feature = "rayon")]
+    "rayon",
+    #[cfg(feature = "rand_core")]
+    "rand_core",
+    #[cfg(feature = "fork-safe")]
+    "fork-safe",
+    #[cfg(feature = "testing")]
+    "testing",
+    #[cfg(feature = "atomic-polyfill")]
+    "atomic-polyfill",
+];
+
+/// Returns a description of the algorithm and seeds used by aHash in this binary.
+///
+/// Which algorithm is used depends on the target, the features that are enabled, and (with `runtime-dispatch`) the
+/// CPU the program is running on, so this can be used to check what a particular deployment is actually using.
+///
+/// This only reads the state of aHash, so it cannot fail or panic, and it does not obtain the process wide seeds or
+/// reseed after a fork. If no [RandomState](crate::RandomState) has been created yet the seed source is
+/// [SeedSource::NotSeeded].
+///
+/// # Example
+/// ```
+/// let config = ahash::config();
+/// println!("{}", config);
+/// ```
+pub fn config() -> Config {
+    cfg_if::cfg_if! {
+        if #[cfg(feature = "force-fallback")] {
+            let (backend, runtime_dispatch) = (Backend::Fallback, false);
+        } else if #[cfg(any(
+            all(any(target_arch = "x86", target_arch = "x86_64"), target_feature = "aes", not(miri)),
+            all(any(target_arch = "arm", target_arch = "aarch64"), any(target_feature = "aes", target_feature = "crypto"), not(miri), feature = "stdsimd")
+        ))] {
+            let (backend, runtime_dispatch) = (Backend::Aes, false);
+        } else if #[cfg(all(feature = "runtime-dispatch", any(target_arch = "x86", target_arch = "x86_64"), not(miri)))] {
+            let backend = if crate::dispatch_hash::has_aes() { Backend::Aes } else { Backend::Fallback };
+            let runtime_dispatch = true;
+        } else {
+            let (backend, runtime_dispatch) = (Backend::Fallback, false);
+        }
+    }
+    Config {
+        backend,
+        runtime_dispatch,
+        specialize: cfg!(feature = "specialize"),
+        folded_multiply: cfg!(feature = "folded_multiply"),
+        seed_source: crate::random_state::seed_source(),
+        reseeded_after_fork: crate::random_state::reseeded_after_fork(),
+        custom_random_source: crate::random_state::has_custom_source(),
+        features: FEATURES,
+    }
+}
+
+impl fmt::Display for Backend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Backend::Aes => "aes",
+            Backend::Fallback => "fallback",
+        })
+    }
+}
+
+impl fmt::Display for SeedSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            SeedSource::Runtime => "runtime",
+            SeedSource::CompileTime => "compile time",
+            SeedSource::Fixed => "fixed",
+            SeedSource::Deterministic => "deterministic",
+            SeedSource::FailurePolicy => "failure policy",
+            SeedSource::NotSeeded => "not seeded",
+        })
+    }
+}
+
+impl fmt::Display for Config {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "backend: {}", self.backend)?;
+        if self.runtime_dispatch {
+            f.write_str(" (runtime dispatch)")?;
+        }
+        write!(
+            f,
+            ", specialize: {}, folded multiply: {}, seeds: {}",
+            self.specialize, self.folded_multiply, self.seed_source
+        )?;
+        if self.reseeded_after_fork {
+            f.write_str(" (reseeded after fork)")?;
+        }
+        write!(
+            f,
+            ", random source: {}, features: [{}]",
+            if self.custom_random_source { "custom" } else { "default" },
+            FeatureList(self.features),
+        )
+    }
+}
+
+struct FeatureList(&'static [&'static str]);
+
+impl fmt::Display for FeatureList {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, feature) in self.0.iter().enumerate() {
+            if i > 0 {
+                f.write_str(", ")?;
+            }
+            f.write_str(feature)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::AHasher;
+
+    #[test]
+    fn test_backend_matches_hasher() {
+        let state = AHasher::default().export_state();
+        let aes = state[1] == crate::hasher_state::AES;
+        assert_eq!(aes, config().backend == Backend::Aes);
+    }
+
+    #[test]
+    fn test_config() {
+        // Other tests may or may not have created a state already, so one is created to make the source known.
+        let _ = crate::RandomState::new();
+        let config = config();
+        assert_eq!(config.features.contains(&"std"), cfg!(feature = "std"));
+        assert_ne!(config.seed_source, SeedSource::NotSeeded);
+        #[cfg(all(feature = "runtime-rng", not(feature = "compile-time-rng"), not(feature = "testing")))]
+        assert_eq!(config.seed_source, SeedSource::Runtime);
+        let description = config.to_string();
+        assert!(description.starts_with(&format!("backend: {}", config.backend)));
+        assert!(description.ends_with("]"));
+    }
+}
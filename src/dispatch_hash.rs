@@ -0,0 +1,528 @@
+use crate::aes_hash;
+use crate::fallback_hash;
+use crate::hasher_state::{ImportStateError, STATE_LEN};
+#[cfg(feature = "specialize")]
+use crate::operations::hash_by_4;
+use crate::operations::SoftAes;
+use crate::RandomState;
+use core::hash::Hash;
+use core::hash::Hasher;
+use once_cell::race::OnceBool;
+
+pub(crate) use crate::fallback_hash::AHasherU64;
+
+/// Returns true if the CPU this process is running on supports the AES instructions.
+/// The check is only performed once, subsequent calls read the cached result.
+#[inline]
+pub(crate) fn has_aes() -> bool {
+    static HAS_AES: OnceBool = OnceBool::new();
+    HAS_AES.get_or_init(|| std::is_x86_feature_detected!("aes"))
+}
+
+/// The functions in this module are compiled with the AES instructions enabled so that
+/// the hasher (and the intrinsics it uses) can be inlined into them.
+///
+/// # Safety
+/// They may only be invoked after [has_aes] has returned true.
+mod aes {
+    use crate::aes_hash::AHasher;
+    #[cfg(feature = "specialize")]
+    use crate::operations::hash_by_4;
+    use core::hash::Hash;
+    use core::hash::Hasher;
+
+    #[target_feature(enable = "aes")]
+    pub(super) unsafe fn write(hasher: &mut AHasher, input: &[u8]) {
+        hasher.write(input)
+    }
+
+    #[target_feature(enable = "aes")]
+    pub(super) unsafe fn write_u64(hasher: &mut AHasher, i: u64) {
+        hasher.write_u64(i)
+    }
+
+    #[target_feature(enable = "aes")]
+    pub(super) unsafe fn write_u128(hasher: &mut AHasher, i: u128) {
+        hasher.write_u128(i)
+    }
+
+    #[target_feature(enable = "aes")]
+    pub(super) unsafe fn finish(hasher: &AHasher) -> u64 {
+        hasher.finish()
+    }
+
+    #[target_feature(enable = "aes")]
+    pub(super) unsafe fn finish_u128(hasher: &AHasher) -> u128 {
+        hasher.finish_u128()
+    }
+
+    #[target_feature(enable = "aes")]
+    pub(super) unsafe fn hash_as_fixed_length<T: Hash + ?Sized>(hasher: AHasher, value: &T) -> u64 {
+        let mut hasher = crate::aes_hash::AHasherFixed(hasher);
+        value.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    #[target_feature(enable = "aes")]
+    pub(super) unsafe fn hash_as_str<T: Hash + ?Sized>(hasher: AHasher, value: &T) -> u64 {
+        let mut hasher = crate::aes_hash::AHasherStr(hasher);
+        value.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    #[cfg(feature = "specialize")]
+    #[target_feature(enable = "aes")]
+    pub(super) unsafe fn hash_many_u128(hasher: AHasher, values: &[u128], hashes: &mut [u64]) {
+        hash_by_4(&crate::aes_hash::AHasherFixed(hasher), values, hashes);
+    }
+
+    #[cfg(feature = "specialize")]
+    #[target_feature(enable = "aes")]
+    pub(super) unsafe fn hash_many_bytes<B: AsRef<[u8]>>(hasher: AHasher, values: &[B], hashes: &mut [u64]) {
+        hash_by_4(&crate::aes_hash::AHasherStr(hasher), values, hashes);
+    }
+}
+
+/// A `Hasher` for hashing an arbitrary stream of bytes.
+///
+/// Instances of [`AHasher`] represent state that is updated while hashing data.
+///
+/// Each method updates the internal state based on the new data provided. Once
+/// all of the data has been provided, the resulting hash can be obtained by calling
+/// `finish()`
+///
+/// [Clone] is also provided in case you wish to calculate hashes for two different items that
+/// start with the same data.
+///
+/// This build was compiled with the `runtime-dispatch` feature, so the algorithm used (AES or fallback)
+/// is selected when the hasher is created based on the features of the CPU that is running it.
+#[derive(Debug, Clone)]
+pub struct AHasher(Inner);
+
+#[derive(Debug, Clone)]
+enum Inner {
+    Aes(aes_hash::AHasher),
+    Fallback(fallback_hash::AHasher),
+}
+
+impl AHasher {
+    #[inline]
+    #[allow(dead_code)] // Only used by tests.
+    pub(crate) fn new_with_keys(key1: u128, key2: u128) -> Self {
+        if has_aes() {
+            AHasher(Inner::Aes(aes_hash::AHasher::new_with_keys(key1, key2)))
+        } else {
+            AHasher(Inner::Fallback(fallback_hash::AHasher::new_with_keys(key1, key2)))
+        }
+    }
+
+    #[allow(unused)] // False positive
+    pub(crate) fn test_with_keys(key1: u128, key2: u128) -> Self {
+        if has_aes() {
+            AHasher(Inner::Aes(aes_hash::AHasher::test_with_keys(key1, key2)))
+        } else {
+            AHasher(Inner::Fallback(fallback_hash::AHasher::test_with_keys(key1, key2)))
+        }
+    }
+
+    #[inline]
+    pub(crate) fn from_random_state(rand_state: &RandomState) -> Self {
+        if has_aes() {
+            AHasher(Inner::Aes(aes_hash::AHasher::from_random_state(rand_state)))
+        } else {
+            AHasher(Inner::Fallback(fallback_hash::AHasher::from_random_state(rand_state)))
+        }
+    }
+
+    /// Returns a 128 bit hash of the values written so far.
+    ///
+    /// This is useful where 64 bits would produce too many collisions, such as for fingerprinting content or for
+    /// double hashing. The lower 64 bits are the same as those returned by `finish()`.
+    #[inline]
+    pub fn finish_u128(&self) -> u128 {
+        match &self.0 {
+            Inner::Aes(hasher) => unsafe { aes::finish_u128(hasher) },
+            Inner::Fallback(hasher) => hasher.finish_u128(),
+        }
+    }
+
+    /// Saves the current state of the hasher, so that it can be resumed later with [AHasher::import_state].
+    ///
+    /// This allows a long input to be hashed in parts, possibly in different processes. The state can only be
+    /// imported by a build of the same version of aHash which uses the same algorithm. (An [AHasher] resumed in this
+    /// way produces the same result as the original would have)
+    ///
+    /// The state contains the keys of the hasher, so it should be kept secret if DOS resistance is desired.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::hash::{BuildHasher, Hasher};
+    /// use ahash::{AHasher, RandomState};
+    ///
+    /// let mut hasher = RandomState::with_seeds(1, 2, 3, 4).build_hasher();
+    /// hasher.write(b"The first part");
+    /// let saved = hasher.export_state();
+    ///
+    /// let mut resumed = AHasher::import_state(&saved).unwrap();
+    /// hasher.write(b"The second part");
+    /// resumed.write(b"The second part");
+    /// assert_eq!(hasher.finish(), resumed.finish());
+    /// ```
+    #[inline]
+    pub fn export_state(&self) -> [u8; STATE_LEN] {
+        match &self.0 {
+            Inner::Aes(hasher) => hasher.export_state(),
+            Inner::Fallback(hasher) => hasher.export_state(),
+        }
+    }
+
+    /// Recreates a hasher from a state returned by [AHasher::export_state].
+    ///
+    /// This fails if the state is malformed, or if it was exported by a different algorithm than the one this hasher
+    /// uses. (Such as on a CPU with different features)
+    #[inline]
+    pub fn import_state(state: &[u8]) -> Result<AHasher, ImportStateError> {
+        if has_aes() {
+            aes_hash::AHasher::import_state(state).map(|hasher| AHasher(Inner::Aes(hasher)))
+        } else {
+            fallback_hash::AHasher::import_state(state).map(|hasher| AHasher(Inner::Fallback(hasher)))
+        }
+    }
+}
+
+/// Provides [Hasher] methods to hash all of the primitive types.
+///
+/// [Hasher]: core::hash::Hasher
+impl Hasher for AHasher {
+    #[inline]
+    fn write_u8(&mut self, i: u8) {
+        self.write_u64(i as u64);
+    }
+
+    #[inline]
+    fn write_u16(&mut self, i: u16) {
+        self.write_u64(i as u64);
+    }
+
+    #[inline]
+    fn write_u32(&mut self, i: u32) {
+        self.write_u64(i as u64);
+    }
+
+    #[inline]
+    fn write_u64(&mut self, i: u64) {
+        match &mut self.0 {
+            Inner::Aes(hasher) => unsafe { aes::write_u64(hasher, i) },
+            Inner::Fallback(hasher) => hasher.write_u64(i),
+        }
+    }
+
+    #[inline]
+    fn write_u128(&mut self, i: u128) {
+        match &mut self.0 {
+            Inner::Aes(hasher) => unsafe { aes::write_u128(hasher, i) },
+            Inner::Fallback(hasher) => hasher.write_u128(i),
+        }
+    }
+
+    #[inline]
+    #[cfg(any(
+        target_pointer_width = "64",
+        target_pointer_width = "32",
+        target_pointer_width = "16"
+    ))]
+    fn write_usize(&mut self, i: usize) {
+        self.write_u64(i as u64);
+    }
+
+    #[inline]
+    #[cfg(target_pointer_width = "128")]
+    fn write_usize(&mut self, i: usize) {
+        self.write_u128(i as u128);
+    }
+
+    #[inline]
+    fn write(&mut self, input: &[u8]) {
+        match &mut self.0 {
+            Inner::Aes(hasher) => unsafe { aes::write(hasher, input) },
+            Inner::Fallback(hasher) => hasher.write(input),
+        }
+    }
+
+    #[inline]
+    fn finish(&self) -> u64 {
+        match &self.0 {
+            Inner::Aes(hasher) => unsafe { aes::finish(hasher) },
+            Inner::Fallback(hasher) => hasher.finish(),
+        }
+    }
+}
+
+/// The hasher using the AES based algorithm.
+///
+/// This build was compiled with the `runtime-dispatch` feature, so the AES instructions are used if the CPU running it
+/// supports them. Otherwise this uses a software implementation of the AES round, which produces the same hashes but
+/// is much slower. (So it should generally not be used in that case except to compare the output)
+#[derive(Debug, Clone)]
+pub struct AesHasher(AesInner);
+
+#[derive(Debug, Clone)]
+enum AesInner {
+    Hardware(aes_hash::AHasher),
+    /// The same hasher, but always computed with the [SoftAes] round.
+    Soft(aes_hash::AHasher),
+}
+
+impl AesHasher {
+    #[inline]
+    #[allow(dead_code)] // Only used by tests.
+    pub(crate) fn new_with_keys(key1: u128, key2: u128) -> Self {
+        if has_aes() {
+            AesHasher(AesInner::Hardware(aes_hash::AHasher::new_with_keys(key1, key2)))
+        } else {
+            AesHasher(AesInner::Soft(aes_hash::AHasher::new_with_keys(key1, key2)))
+        }
+    }
+
+    #[inline]
+    pub(crate) fn from_random_state(rand_state: &RandomState) -> Self {
+        if has_aes() {
+            AesHasher(AesInner::Hardware(aes_hash::AHasher::from_random_state(rand_state)))
+        } else {
+            AesHasher(AesInner::Soft(aes_hash::AHasher::from_random_state(rand_state)))
+        }
+    }
+
+    /// Returns a 128 bit hash of the values written so far.
+    ///
+    /// This is useful where 64 bits would produce too many collisions, such as for fingerprinting content or for
+    /// double hashing. The lower 64 bits are the same as those returned by `finish()`.
+    #[inline]
+    pub fn finish_u128(&self) -> u128 {
+        match &self.0 {
+            AesInner::Hardware(hasher) => unsafe { aes::finish_u128(hasher) },
+            AesInner::Soft(hasher) => hasher.finish_u128_with::<SoftAes>(),
+        }
+    }
+
+    /// Saves the current state of the hasher, so that it can be resumed later with [AesHasher::import_state].
+    ///
+    /// The state is the same whether or not the AES instructions are used, so it can be imported on any CPU. It
+    /// contains the keys of the hasher, so it should be kept secret if DOS resistance is desired.
+    #[inline]
+    pub fn export_state(&self) -> [u8; STATE_LEN] {
+        match &self.0 {
+            AesInner::Hardware(hasher) => hasher.export_state(),
+            AesInner::Soft(hasher) => hasher.export_state(),
+        }
+    }
+
+    /// Recreates a hasher from a state returned by [AesHasher::export_state].
+    ///
+    /// This fails if the state is malformed, or if it was exported by the fallback algorithm.
+    #[inline]
+    pub fn import_state(state: &[u8]) -> Result<AesHasher, ImportStateError> {
+        if has_aes() {
+            aes_hash::AHasher::import_state(state).map(|hasher| AesHasher(AesInner::Hardware(hasher)))
+        } else {
+            aes_hash::AHasher::import_state(state).map(|hasher| AesHasher(AesInner::Soft(hasher)))
+        }
+    }
+}
+
+/// Provides [Hasher] methods to hash all of the primitive types.
+///
+/// [Hasher]: core::hash::Hasher
+impl Hasher for AesHasher {
+    #[inline]
+    fn write_u8(&mut self, i: u8) {
+        self.write_u64(i as u64);
+    }
+
+    #[inline]
+    fn write_u16(&mut self, i: u16) {
+        self.write_u64(i as u64);
+    }
+
+    #[inline]
+    fn write_u32(&mut self, i: u32) {
+        self.write_u64(i as u64);
+    }
+
+    #[inline]
+    fn write_u64(&mut self, i: u64) {
+        match &mut self.0 {
+            AesInner::Hardware(hasher) => unsafe { aes::write_u64(hasher, i) },
+            AesInner::Soft(hasher) => hasher.write_u128_with::<SoftAes>(i as u128),
+        }
+    }
+
+    #[inline]
+    fn write_u128(&mut self, i: u128) {
+        match &mut self.0 {
+            AesInner::Hardware(hasher) => unsafe { aes::write_u128(hasher, i) },
+            AesInner::Soft(hasher) => hasher.write_u128_with::<SoftAes>(i),
+        }
+    }
+
+    #[inline]
+    #[cfg(any(
+        target_pointer_width = "64",
+        target_pointer_width = "32",
+        target_pointer_width = "16"
+    ))]
+    fn write_usize(&mut self, i: usize) {
+        self.write_u64(i as u64);
+    }
+
+    #[inline]
+    #[cfg(target_pointer_width = "128")]
+    fn write_usize(&mut self, i: usize) {
+        self.write_u128(i as u128);
+    }
+
+    #[inline]
+    fn write(&mut self, input: &[u8]) {
+        match &mut self.0 {
+            AesInner::Hardware(hasher) => unsafe { aes::write(hasher, input) },
+            AesInner::Soft(hasher) => hasher.write_with::<SoftAes>(input),
+        }
+    }
+
+    #[inline]
+    fn finish(&self) -> u64 {
+        match &self.0 {
+            AesInner::Hardware(hasher) => unsafe { aes::finish(hasher) },
+            AesInner::Soft(hasher) => hasher.finish_with::<SoftAes>(),
+        }
+    }
+}
+
+/// Hashes a fixed size value larger than 64 bits using the backend selected for this CPU.
+/// The whole hash is computed inside a single function compiled for that backend.
+#[inline]
+pub(crate) fn hash_as_fixed_length<T: Hash + ?Sized>(rand_state: &RandomState, value: &T) -> u64 {
+    if has_aes() {
+        unsafe { aes::hash_as_fixed_length(aes_hash::AHasher::from_random_state(rand_state), value) }
+    } else {
+        let mut hasher = fallback_hash::AHasherFixed(fallback_hash::AHasher::from_random_state(rand_state));
+        value.hash(&mut hasher);
+        hasher.finish()
+    }
+}
+
+/// Hashes a single string or byte slice using the backend selected for this CPU.
+#[inline]
+pub(crate) fn hash_as_str<T: Hash + ?Sized>(rand_state: &RandomState, value: &T) -> u64 {
+    if has_aes() {
+        unsafe { aes::hash_as_str(aes_hash::AHasher::from_random_state(rand_state), value) }
+    } else {
+        let mut hasher = fallback_hash::AHasherStr(fallback_hash::AHasher::from_random_state(rand_state));
+        value.hash(&mut hasher);
+        hasher.finish()
+    }
+}
+
+/// Hashes many fixed size values larger than 64 bits using the backend selected for this CPU.
+#[cfg(feature = "specialize")]
+#[inline]
+pub(crate) fn hash_many_u128(rand_state: &RandomState, values: &[u128], hashes: &mut [u64]) {
+    if has_aes() {
+        unsafe { aes::hash_many_u128(aes_hash::AHasher::from_random_state(rand_state), values, hashes) }
+    } else {
+        let hasher = fallback_hash::AHasherFixed(fallback_hash::AHasher::from_random_state(rand_state));
+        hash_by_4(&hasher, values, hashes);
+    }
+}
+
+/// Hashes many strings or byte slices using the backend selected for this CPU.
+#[cfg(feature = "specialize")]
+#[inline]
+pub(crate) fn hash_many_bytes<B: AsRef<[u8]>>(rand_state: &RandomState, values: &[B], hashes: &mut [u64]) {
+    if has_aes() {
+        unsafe { aes::hash_many_bytes(aes_hash::AHasher::from_random_state(rand_state), values, hashes) }
+    } else {
+        let hasher = fallback_hash::AHasherStr(fallback_hash::AHasher::from_random_state(rand_state));
+        hash_by_4(&hasher, values, hashes);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::hash::BuildHasher;
+
+    fn expected(rand_state: &RandomState, data: &[u8]) -> u64 {
+        if has_aes() {
+            let mut hasher = aes_hash::AHasher::from_random_state(rand_state);
+            unsafe { aes::write(&mut hasher, data) };
+            unsafe { aes::finish(&hasher) }
+        } else {
+            let mut hasher = fallback_hash::AHasher::from_random_state(rand_state);
+            hasher.write(data);
+            hasher.finish()
+        }
+    }
+
+    #[test]
+    fn test_detection_is_stable() {
+        assert_eq!(has_aes(), has_aes());
+        assert_eq!(has_aes(), std::is_x86_feature_detected!("aes"));
+    }
+
+    #[test]
+    fn test_matches_selected_backend() {
+        let rand_state = RandomState::with_seeds(1, 2, 3, 4);
+        for len in [0, 1, 7, 8, 9, 16, 17, 32, 33, 64, 65, 200] {
+            let data: Vec<u8> = (0..len as u8).collect();
+            let mut hasher = rand_state.build_hasher();
+            hasher.write(&data);
+            assert_eq!(expected(&rand_state, &data), hasher.finish(), "Length {}", len);
+        }
+    }
+
+    #[test]
+    fn test_backend_selected() {
+        let hasher = RandomState::with_seeds(1, 2, 3, 4).build_hasher();
+        match hasher.0 {
+            Inner::Aes(_) => assert!(has_aes()),
+            Inner::Fallback(_) => assert!(!has_aes()),
+        }
+    }
+
+    #[test]
+    fn test_export_import_state() {
+        let mut hasher = RandomState::with_seeds(1, 2, 3, 4).build_hasher();
+        hasher.write(b"first");
+        let mut resumed = AHasher::import_state(&hasher.export_state()).unwrap();
+        hasher.write(b"second");
+        resumed.write(b"second");
+        assert_eq!(hasher.finish(), resumed.finish());
+
+        let other = if has_aes() {
+            fallback_hash::AHasher::new_with_keys(1, 2).export_state()
+        } else {
+            aes_hash::AHasher::new_with_keys(1, 2).export_state()
+        };
+        assert_eq!(Some(ImportStateError::BackendMismatch), AHasher::import_state(&other).err());
+    }
+
+    #[test]
+    fn test_soft_aes_matches_hardware() {
+        let rand_state = RandomState::with_seeds(1, 2, 3, 4);
+        let mut aes = AesHasher::from_random_state(&rand_state);
+        let mut soft = AesHasher(AesInner::Soft(aes_hash::AHasher::from_random_state(&rand_state)));
+        for len in [0, 1, 7, 8, 9, 16, 17, 32, 33, 64, 65, 200] {
+            let data: Vec<u8> = (0..len as u8).collect();
+            aes.write(&data);
+            soft.write(&data);
+            aes.write_u64(len);
+            soft.write_u64(len);
+            assert_eq!(aes.finish(), soft.finish(), "Length {}", len);
+            assert_eq!(aes.finish_u128(), soft.finish_u128(), "Length {}", len);
+        }
+        let resumed = AesHasher::import_state(&soft.export_state()).unwrap();
+        assert_eq!(soft.finish(), resumed.finish());
+    }
+}
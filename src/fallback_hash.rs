@@ -1,6 +1,8 @@
 
 use crate::convert::*;
+use crate::hasher_state::{self, ImportStateError, STATE_LEN};
 use crate::operations::folded_multiply;
+use crate::operations::HashX4;
 use crate::operations::read_small;
 use crate::operations::MULTIPLE;
 use crate::random_state::PI;
@@ -115,8 +117,74 @@ impl AHasher {
         self.buffer = (self.buffer.wrapping_add(self.pad) ^ combined).rotate_left(ROT);
     }
 
+    /// Returns a 128 bit hash of the values written so far.
+    ///
+    /// This is useful where 64 bits would produce too many collisions, such as for fingerprinting content or for
+    /// double hashing. The lower 64 bits are the same as those returned by `finish()`.
+    ///
+    /// Note that the fallback algorithm only carries 64 bits of state between updates, so while the two halves are
+    /// mixed independently, inputs which collide in that state will also collide here.
+    #[inline]
+    #[allow(dead_code)] // Is not called if non-fallback hash is used.
+    pub fn finish_u128(&self) -> u128 {
+        let rot = (self.buffer & 63) as u32;
+        let low = folded_multiply(self.buffer, self.pad).rotate_left(rot);
+        let high = folded_multiply(self.buffer.rotate_left(ROT) ^ self.extra_keys[0], self.pad ^ self.extra_keys[1]);
+        [low, high.rotate_left(rot)].convert()
+    }
+
+    /// Saves the current state of the hasher, so that it can be resumed later with [AHasher::import_state].
+    ///
+    /// This allows a long input to be hashed in parts, possibly in different processes. The state can only be
+    /// imported by a build of the same version of aHash which uses the same algorithm. (An [AHasher] resumed in this
+    /// way produces the same result as the original would have)
+    ///
+    /// The state contains the keys of the hasher, so it should be kept secret if DOS resistance is desired.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::hash::{BuildHasher, Hasher};
+    /// use ahash::{AHasher, RandomState};
+    ///
+    /// let mut hasher = RandomState::with_seeds(1, 2, 3, 4).build_hasher();
+    /// hasher.write(b"The first part");
+    /// let saved = hasher.export_state();
+    ///
+    /// let mut resumed = AHasher::import_state(&saved).unwrap();
+    /// hasher.write(b"The second part");
+    /// resumed.write(b"The second part");
+    /// assert_eq!(hasher.finish(), resumed.finish());
+    /// ```
+    #[inline]
+    #[allow(dead_code)] // Is not called if non-fallback hash is used.
+    pub fn export_state(&self) -> [u8; STATE_LEN] {
+        let keys = (self.buffer as u128) | ((self.pad as u128) << 64);
+        let extra_keys = (self.extra_keys[0] as u128) | ((self.extra_keys[1] as u128) << 64);
+        hasher_state::encode(Self::BACKEND, [keys, extra_keys, 0])
+    }
+
+    /// Recreates a hasher from a state returned by [AHasher::export_state].
+    ///
+    /// This fails if the state is malformed, or if it was exported by a different algorithm than the one this hasher
+    /// uses. (Such as by a build with AES instructions)
+    #[inline]
+    #[allow(dead_code)] // Is not called if non-fallback hash is used.
+    pub fn import_state(state: &[u8]) -> Result<AHasher, ImportStateError> {
+        let [keys, extra_keys, _] = hasher_state::decode(Self::BACKEND, state)?;
+        Ok(AHasher {
+            buffer: keys as u64,
+            pad: (keys >> 64) as u64,
+            extra_keys: [extra_keys as u64, (extra_keys >> 64) as u64],
+        })
+    }
+
+    #[cfg(feature = "folded_multiply")]
+    const BACKEND: u8 = hasher_state::FALLBACK_FOLDED;
+    #[cfg(not(feature = "folded_multiply"))]
+    const BACKEND: u8 = hasher_state::FALLBACK_WRAPPING;
+
     #[inline]
-    #[cfg(feature = "specialize")]
     fn short_finish(&self) -> u64 {
         self.buffer.wrapping_add(self.pad)
     }
@@ -200,14 +268,13 @@ impl Hasher for AHasher {
     }
 }
 
-#[cfg(feature = "specialize")]
+#[allow(dead_code)] // Depends on the backend
 pub(crate) struct AHasherU64 {
     pub(crate) buffer: u64,
     pub(crate) pad: u64,
 }
 
 /// A specialized hasher for only primitives under 64 bits.
-#[cfg(feature = "specialize")]
 impl Hasher for AHasherU64 {
     #[inline]
     fn finish(&self) -> u64 {
@@ -251,11 +318,34 @@ impl Hasher for AHasherU64 {
     }
 }
 
-#[cfg(feature = "specialize")]
+impl HashX4<u64> for AHasherU64 {
+    /// Hashes four values in lockstep, with the same results as writing each of them to a copy of this hasher.
+    #[inline(always)]
+    fn hash_x4(&self, values: [&u64; 4]) -> [u64; 4] {
+        let lane = |value: u64| {
+            let mut hasher = AHasherU64 {
+                buffer: self.buffer,
+                pad: self.pad,
+            };
+            hasher.write_u64(value);
+            hasher.finish()
+        };
+        [lane(*values[0]), lane(*values[1]), lane(*values[2]), lane(*values[3])]
+    }
+}
+
+impl HashX4<u32> for AHasherU64 {
+    #[inline(always)]
+    fn hash_x4(&self, values: [&u32; 4]) -> [u64; 4] {
+        let values = [*values[0] as u64, *values[1] as u64, *values[2] as u64, *values[3] as u64];
+        self.hash_x4([&values[0], &values[1], &values[2], &values[3]])
+    }
+}
+
+#[allow(dead_code)] // Depends on the backend
 pub(crate) struct AHasherFixed(pub AHasher);
 
 /// A specialized hasher for fixed size primitives larger than 64 bits.
-#[cfg(feature = "specialize")]
 impl Hasher for AHasherFixed {
     #[inline]
     fn finish(&self) -> u64 {
@@ -298,12 +388,24 @@ impl Hasher for AHasherFixed {
     }
 }
 
-#[cfg(feature = "specialize")]
+impl HashX4<u128> for AHasherFixed {
+    /// Hashes four values in lockstep, with the same results as writing each of them to a copy of this hasher.
+    #[inline(always)]
+    fn hash_x4(&self, values: [&u128; 4]) -> [u64; 4] {
+        let lane = |value: u128| {
+            let mut hasher = AHasherFixed(self.0.clone());
+            hasher.write_u128(value);
+            hasher.finish()
+        };
+        [lane(*values[0]), lane(*values[1]), lane(*values[2]), lane(*values[3])]
+    }
+}
+
+#[allow(dead_code)] // Depends on the backend
 pub(crate) struct AHasherStr(pub AHasher);
 
 /// A specialized hasher for a single string
 /// Note that the other types don't panic because the hash impl for String tacks on an unneeded call. (As does vec)
-#[cfg(feature = "specialize")]
 impl Hasher for AHasherStr {
     #[inline]
     fn finish(&self) -> u64 {
@@ -315,9 +417,7 @@ impl Hasher for AHasherStr {
         if bytes.len() > 8 {
             self.0.write(bytes)
         } else {
-            let value = read_small(bytes);
-            self.0.buffer = folded_multiply(value[0] ^ self.0.buffer, value[1] ^ self.0.extra_keys[1]);
-            self.0.pad = self.0.pad.wrapping_add(bytes.len() as u64);
+            self.write_short(bytes)
         }
     }
 
@@ -340,6 +440,47 @@ impl Hasher for AHasherStr {
     fn write_usize(&mut self, _i: usize) {}
 }
 
+impl AHasherStr {
+    #[inline(always)]
+    #[allow(dead_code)] // Depends on the backend
+    fn write_short(&mut self, bytes: &[u8]) {
+        let value = read_small(bytes);
+        self.0.buffer = folded_multiply(value[0] ^ self.0.buffer, value[1] ^ self.0.extra_keys[1]);
+        self.0.pad = self.0.pad.wrapping_add(bytes.len() as u64);
+    }
+
+    /// Kept out of line so that `hash_x4` is small enough to inline into the caller's loop.
+    #[inline(never)]
+    #[allow(dead_code)] // Depends on the backend
+    fn hash_long_x4(&self, values: [&[u8]; 4]) -> [u64; 4] {
+        let lane = |value: &[u8]| {
+            let mut hasher = AHasherStr(self.0.clone());
+            hasher.write(value);
+            hasher.finish()
+        };
+        [lane(values[0]), lane(values[1]), lane(values[2]), lane(values[3])]
+    }
+}
+
+impl<B: AsRef<[u8]>> HashX4<B> for AHasherStr {
+    /// Hashes four values in lockstep, with the same results as writing each of them to a copy of this hasher.
+    /// (Checking the lengths once for the whole group, so that short inputs take the same path in every lane)
+    #[inline(always)]
+    fn hash_x4(&self, values: [&B; 4]) -> [u64; 4] {
+        let values = [values[0].as_ref(), values[1].as_ref(), values[2].as_ref(), values[3].as_ref()];
+        if values.iter().all(|value| value.len() <= 8) {
+            let lane = |value: &[u8]| {
+                let mut hasher = AHasherStr(self.0.clone());
+                hasher.write_short(value);
+                hasher.finish()
+            };
+            [lane(values[0]), lane(values[1]), lane(values[2]), lane(values[3])]
+        } else {
+            self.hash_long_x4(values)
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::convert::Convert;
@@ -366,4 +507,37 @@ mod tests {
         let bytes: u64 = as_array!(input, 8).convert();
         assert_eq!(bytes, 0x6464646464646464);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_export_import_state() {
+        let mut hasher = AHasher::new_with_keys(1234, 5678);
+        hasher.write(b"first");
+        hasher.write_u64(42);
+        let state = hasher.export_state();
+        let mut resumed = AHasher::import_state(&state).unwrap();
+        assert_eq!(hasher.finish_u128(), resumed.finish_u128());
+        hasher.write(b"second");
+        resumed.write(b"second");
+        assert_eq!(hasher.finish(), resumed.finish());
+        assert_eq!(state, AHasher::import_state(&state).unwrap().export_state());
+    }
+
+    #[test]
+    fn test_import_rejects_other_version() {
+        use crate::hasher_state::ImportStateError;
+
+        let mut state = AHasher::new_with_keys(1234, 5678).export_state();
+        state[0] ^= 1;
+        assert_eq!(Some(ImportStateError::UnsupportedVersion), AHasher::import_state(&state).err());
+    }
+
+    #[test]
+    fn test_import_rejects_other_backend() {
+        use crate::hasher_state::{self, ImportStateError};
+
+        let aes_state = hasher_state::encode(hasher_state::AES, [1, 2, 3]);
+        assert_eq!(Some(ImportStateError::BackendMismatch), AHasher::import_state(&aes_state).err());
+        let state = AHasher::new_with_keys(1, 2).export_state();
+        assert_eq!(Some(ImportStateError::InvalidLength), AHasher::import_state(&state[..10]).err());
+    }
+}
@@ -0,0 +1,162 @@
+//! Implementations of the [digest] traits. (Requires the `digest` feature)
+use crate::{AHashStream, RandomState};
+use core::fmt;
+use core::hash::Hasher;
+use digest::consts::{U16, U32, U8};
+use digest::crypto_common::{Key, KeyInit, KeySizeUser};
+use digest::{FixedOutput, FixedOutputReset, Output, OutputSizeUser, Reset, Update};
+
+macro_rules! impl_digest {
+    ($name:ident, $size:ty, $bits:literal, $finish:ident) => {
+        #[doc = concat!("A keyed ", $bits, " bit [digest] built on [AHashStream].")]
+        ///
+        /// This allows aHash to be used with code written against the traits of the `digest` crate, such as
+        /// [Update], [FixedOutput] and [KeyInit]. Like an `AHashStream` the result only depends on the concatenation of
+        /// all of the data passed to `update`, not on how it was split up. The output is the hash in little-endian
+        /// order.
+        ///
+        /// A key of 32 bytes is read as four little-endian `u64`s and passed to [RandomState::with_seeds]. The
+        /// [Default] instance uses the same fixed keys as [AHasher::default](crate::AHasher).
+        ///
+        /// Note that aHash is not a cryptographic hash. Because of this [HashMarker](digest::HashMarker) is not
+        /// implemented, and the output should not be relied upon where an attacker can see it.
+        #[derive(Clone)]
+        pub struct $name {
+            state: RandomState,
+            stream: AHashStream,
+        }
+
+        impl $name {
+            /// Creates a digest which hashes using the keys of the provided [RandomState].
+            #[inline]
+            pub fn with_state(state: RandomState) -> Self {
+                let stream = AHashStream::new(&state);
+                $name { state, stream }
+            }
+
+            #[inline]
+            fn output(&self) -> [u8; $bits / 8] {
+                self.stream.$finish().to_le_bytes()
+            }
+        }
+
+        impl Default for $name {
+            #[inline]
+            fn default() -> Self {
+                Self::with_state(RandomState::with_fixed_keys())
+            }
+        }
+
+        impl fmt::Debug for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.pad(concat!(stringify!($name), " { .. }"))
+            }
+        }
+
+        impl OutputSizeUser for $name {
+            type OutputSize = $size;
+        }
+
+        impl KeySizeUser for $name {
+            type KeySize = U32;
+        }
+
+        impl KeyInit for $name {
+            #[inline]
+            fn new(key: &Key<Self>) -> Self {
+                Self::with_state(state_from_key(key))
+            }
+        }
+
+        impl Update for $name {
+            #[inline]
+            fn update(&mut self, data: &[u8]) {
+                self.stream.write(data);
+            }
+        }
+
+        impl FixedOutput for $name {
+            #[inline]
+            fn finalize_into(self, out: &mut Output<Self>) {
+                out.copy_from_slice(&self.output());
+            }
+        }
+
+        impl Reset for $name {
+            #[inline]
+            fn reset(&mut self) {
+                self.stream = AHashStream::new(&self.state);
+            }
+        }
+
+        impl FixedOutputReset for $name {
+            #[inline]
+            fn finalize_into_reset(&mut self, out: &mut Output<Self>) {
+                out.copy_from_slice(&self.output());
+                self.reset();
+            }
+        }
+    };
+}
+
+impl_digest!(AHashDigest64, U8, 64, finish);
+impl_digest!(AHashDigest128, U16, 128, finish_u128);
+
+#[inline]
+fn state_from_key(key: &[u8]) -> RandomState {
+    let mut seeds = [0_u64; 4];
+    for (seed, bytes) in seeds.iter_mut().zip(key.chunks_exact(8)) {
+        let mut word = [0; 8];
+        word.copy_from_slice(bytes);
+        *seed = u64::from_le_bytes(word);
+    }
+    RandomState::with_seeds(seeds[0], seeds[1], seeds[2], seeds[3])
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_matches_stream() {
+        let key: [u8; 32] = core::array::from_fn(|i| i as u8);
+        let state = state_from_key(&key);
+        let mut stream = AHashStream::new(&state);
+        stream.write(b"Hello, world!");
+
+        let mut digest = <AHashDigest64 as KeyInit>::new(&key.into());
+        digest.update(b"Hello, ");
+        digest.update(b"world!");
+        assert_eq!(stream.finish().to_le_bytes(), digest.finalize_fixed()[..]);
+
+        let mut digest = AHashDigest128::new_from_slice(&key).unwrap();
+        digest.update(b"Hello, world!");
+        assert_eq!(stream.finish_u128().to_le_bytes(), digest.finalize_fixed()[..]);
+        assert!(AHashDigest128::new_from_slice(&key[1..]).is_err());
+    }
+
+    #[test]
+    fn test_key_and_reset() {
+        let mut a = AHashDigest64::new(&[1; 32].into());
+        let mut b = AHashDigest64::new(&[2; 32].into());
+        a.update(b"data");
+        let first = a.finalize_fixed_reset();
+        a.update(b"data");
+        assert_eq!(first, a.clone().finalize_fixed());
+        a.reset();
+        assert_eq!(a.clone().finalize_fixed(), AHashDigest64::new(&[1; 32].into()).finalize_fixed());
+        b.update(b"data");
+        assert_ne!(first, b.finalize_fixed());
+    }
+
+    #[test]
+    fn test_seeds_from_key() {
+        let mut key = [0_u8; 32];
+        key[0] = 1;
+        key[8] = 2;
+        key[16] = 3;
+        key[24] = 4;
+        let expected = RandomState::with_seeds(1, 2, 3, 4);
+        assert_eq!(expected.to_seeds(), state_from_key(&key).to_seeds());
+    }
+}
@@ -3,8 +3,10 @@ use std::borrow::Borrow;
 use std::collections::hash_map::{IntoKeys, IntoValues};
 use std::collections::{hash_map, HashMap};
 use std::fmt::{self, Debug};
-use std::hash::{BuildHasher, Hash};
+use std::hash::{BuildHasher, Hash, Hasher};
 use std::iter::FromIterator;
+#[cfg(feature = "serde")]
+use std::marker::PhantomData;
 use std::ops::{Deref, DerefMut, Index};
 use std::panic::UnwindSafe;
 
@@ -315,6 +317,22 @@ where
 {
 }
 
+/// Hashes the entries in a way that does not depend on their order, so that maps which are equal hash the same even
+/// if they have different hashers. (See [MultisetHash](crate::MultisetHash)) The keys used to hash each entry are
+/// derived from the hasher that the map is written to, so they are as secret as its keys.
+impl<K, V, S> Hash for AHashMap<K, V, S>
+where
+    K: Eq + Hash,
+    V: Hash,
+    S: BuildHasher,
+{
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        let mut entries = crate::MultisetHash::keyed_by(state);
+        entries.extend(self.0.iter());
+        entries.write_to(state);
+    }
+}
+
 impl<K, Q: ?Sized, V, S> Index<&Q> for AHashMap<K, V, S>
 where
     K: Eq + Hash + Borrow<Q>,
@@ -473,6 +491,171 @@ where
     }
 }
 
+/// Serializing the [RandomState] along with the entries.
+///
+/// By default an `AHashMap` is serialized as a plain map, and a deserialized map gets new random keys. These functions
+/// instead store the keys of the map's [RandomState] next to the entries, so the deserialized map hashes every key
+/// exactly as the original did. They can be used with serde's `with` attributes:
+///
+/// ```
+/// use ahash::AHashMap;
+///
+/// #[derive(serde::Serialize, serde::Deserialize)]
+/// struct Index {
+///     #[serde(serialize_with = "AHashMap::serialize_with_state")]
+///     #[serde(deserialize_with = "AHashMap::deserialize_with_state")]
+///     entries: AHashMap<String, u64>,
+/// }
+/// ```
+///
+/// Note that this exposes the keys to anyone who can read the serialized form. (See [RandomState::to_seeds])
+#[cfg(feature = "serde")]
+impl<K, V> AHashMap<K, V> {
+    /// Serializes the map as a struct containing its `state` followed by its `entries`.
+    pub fn serialize_with_state<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        K: Serialize + Eq + Hash,
+        V: Serialize,
+    {
+        use serde::ser::SerializeStruct;
+
+        let mut state = serializer.serialize_struct("AHashMap", 2)?;
+        state.serialize_field("state", self.hasher())?;
+        state.serialize_field("entries", &self.0)?;
+        state.end()
+    }
+
+    /// Deserializes a map written by [AHashMap::serialize_with_state], using the stored [RandomState].
+    pub fn deserialize_with_state<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error>
+    where
+        K: Deserialize<'de> + Eq + Hash,
+        V: Deserialize<'de>,
+    {
+        deserializer.deserialize_struct("AHashMap", STATE_FIELDS, with_state::MapWithStateVisitor(PhantomData))
+    }
+}
+
+#[cfg(feature = "serde")]
+const STATE_FIELDS: &[&str] = &["state", "entries"];
+
+#[cfg(feature = "serde")]
+mod with_state {
+    use super::*;
+    use serde::de::{DeserializeSeed, Error, MapAccess, SeqAccess, Visitor};
+
+    /// Deserializes the entries directly into a map using the given state.
+    struct Entries<K, V>(RandomState, PhantomData<(K, V)>);
+
+    impl<'de, K, V> DeserializeSeed<'de> for Entries<K, V>
+    where
+        K: Deserialize<'de> + Eq + Hash,
+        V: Deserialize<'de>,
+    {
+        type Value = HashMap<K, V, RandomState>;
+
+        fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+            deserializer.deserialize_map(self)
+        }
+    }
+
+    impl<'de, K, V> Visitor<'de> for Entries<K, V>
+    where
+        K: Deserialize<'de> + Eq + Hash,
+        V: Deserialize<'de>,
+    {
+        type Value = HashMap<K, V, RandomState>;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            formatter.write_str("a map")
+        }
+
+        fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+            let mut result = HashMap::with_capacity_and_hasher(map.size_hint().unwrap_or(0).min(4096), self.0);
+            while let Some((key, value)) = map.next_entry()? {
+                result.insert(key, value);
+            }
+            Ok(result)
+        }
+    }
+
+    enum Field {
+        State,
+        Entries,
+    }
+
+    impl<'de> Deserialize<'de> for Field {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            struct FieldVisitor;
+
+            impl<'de> Visitor<'de> for FieldVisitor {
+                type Value = Field;
+
+                fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                    formatter.write_str("`state` or `entries`")
+                }
+
+                fn visit_str<E: Error>(self, value: &str) -> Result<Field, E> {
+                    match value {
+                        "state" => Ok(Field::State),
+                        "entries" => Ok(Field::Entries),
+                        _ => Err(E::unknown_field(value, STATE_FIELDS)),
+                    }
+                }
+            }
+
+            deserializer.deserialize_identifier(FieldVisitor)
+        }
+    }
+
+    pub(super) struct MapWithStateVisitor<K, V>(pub(super) PhantomData<(K, V)>);
+
+    impl<'de, K, V> Visitor<'de> for MapWithStateVisitor<K, V>
+    where
+        K: Deserialize<'de> + Eq + Hash,
+        V: Deserialize<'de>,
+    {
+        type Value = AHashMap<K, V>;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            formatter.write_str("a map with its hasher state")
+        }
+
+        fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+            let state: RandomState = seq.next_element()?.ok_or_else(|| A::Error::invalid_length(0, &self))?;
+            let entries = seq
+                .next_element_seed(Entries(state, PhantomData))?
+                .ok_or_else(|| A::Error::invalid_length(1, &self))?;
+            Ok(AHashMap(entries))
+        }
+
+        fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+            let mut state: Option<RandomState> = None;
+            let mut entries: Option<HashMap<K, V, RandomState>> = None;
+            while let Some(field) = map.next_key()? {
+                match field {
+                    Field::State if state.is_some() => return Err(A::Error::duplicate_field("state")),
+                    Field::State => state = Some(map.next_value()?),
+                    Field::Entries if entries.is_some() => return Err(A::Error::duplicate_field("entries")),
+                    Field::Entries => {
+                        // If the entries come first they have to be rehashed once the state is known.
+                        let seed = state.clone().unwrap_or_else(RandomState::with_fixed_keys);
+                        entries = Some(map.next_value_seed(Entries(seed, PhantomData))?);
+                    }
+                }
+            }
+            let state = state.ok_or_else(|| A::Error::missing_field("state"))?;
+            let entries = entries.ok_or_else(|| A::Error::missing_field("entries"))?;
+            if entries.hasher().to_seeds() == state.to_seeds() {
+                Ok(AHashMap(entries))
+            } else {
+                let mut rehashed = HashMap::with_capacity_and_hasher(entries.len(), state);
+                rehashed.extend(entries);
+                Ok(AHashMap(rehashed))
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -483,6 +666,31 @@ mod test {
         map.insert("Bar".to_string(), map.get("foo").unwrap().to_owned());
     }
 
+    #[test]
+    fn test_hash_is_order_independent() {
+        use crate::RandomState;
+
+        let a: AHashMap<u32, String, _> = (0..100)
+            .map(|i| (i, i.to_string()))
+            .collect::<HashMap<_, _, _>>()
+            .into();
+        let mut b = AHashMap::with_hasher(RandomState::with_seeds(1, 2, 3, 4));
+        for i in (0..100).rev() {
+            b.insert(i, i.to_string());
+        }
+        assert_eq!(a, b);
+        let build_hasher = RandomState::with_seeds(5, 6, 7, 8);
+        assert_eq!(build_hasher.hash_one(&a), build_hasher.hash_one(&b));
+        b.insert(7, "changed".to_string());
+        assert_ne!(build_hasher.hash_one(&a), build_hasher.hash_one(&b));
+        b.remove(&7);
+        assert_ne!(build_hasher.hash_one(&a), build_hasher.hash_one(&b));
+
+        let mut maps = AHashMap::new();
+        maps.insert(a.clone(), 1);
+        assert_eq!(maps.get(&a), Some(&1));
+    }
+
     #[cfg(feature = "serde")]
     #[test]
     fn test_serde() {
@@ -499,4 +707,35 @@ mod test {
         AHashMap::deserialize_in_place(&mut deserializer, &mut deserialization).unwrap();
         assert_eq!(deserialization, map);
     }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_with_state() {
+        let mut map = AHashMap::with_hasher(RandomState::with_seeds(1, 2, 3, 4));
+        for i in 0..100 {
+            map.insert(i.to_string(), i);
+        }
+        let mut serializer = serde_json::Serializer::new(Vec::new());
+        map.serialize_with_state(&mut serializer).unwrap();
+        let serialization = String::from_utf8(serializer.into_inner()).unwrap();
+        assert!(serialization.starts_with("{\"state\":["));
+
+        let mut deserializer = serde_json::Deserializer::from_str(&serialization);
+        let deserialization: AHashMap<String, u64> = AHashMap::deserialize_with_state(&mut deserializer).unwrap();
+        assert_eq!(deserialization, map);
+        assert_eq!(deserialization.hasher().to_seeds(), map.hasher().to_seeds());
+        assert_eq!(deserialization.hasher().hash_one("7"), map.hasher().hash_one("7"));
+
+        // The fields may also be in the other order.
+        let entries = serde_json::to_string(&map).unwrap();
+        let state = serde_json::to_string(map.hasher()).unwrap();
+        let reordered = format!("{{\"entries\":{},\"state\":{}}}", entries, state);
+        let mut deserializer = serde_json::Deserializer::from_str(&reordered);
+        let deserialization: AHashMap<String, u64> = AHashMap::deserialize_with_state(&mut deserializer).unwrap();
+        assert_eq!(deserialization, map);
+        assert_eq!(deserialization.hasher().to_seeds(), map.hasher().to_seeds());
+
+        let mut deserializer = serde_json::Deserializer::from_str(&entries);
+        assert!(AHashMap::<String, u64>::deserialize_with_state(&mut deserializer).is_err());
+    }
 }
\ No newline at end of file
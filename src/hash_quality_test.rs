@@ -342,6 +342,69 @@ fn test_length_extension<T: Hasher>(hasher: impl Fn(u128, u128) -> T) {
     }
 }
 
+/// Provides access to the 128 bit output of the hashers under test.
+trait Hasher128: Hasher {
+    fn finish_u128(&self) -> u128;
+}
+
+fn hash_u128<H: Hash + ?Sized, T: Hasher128>(b: &H, hash_builder: &dyn Fn() -> T) -> u128 {
+    let mut hasher = hash_builder();
+    b.hash(&mut hasher);
+    hasher.finish_u128()
+}
+
+fn assert_sufficiently_different_u128(a: u128, b: u128, tolerance: i32) {
+    assert_sufficiently_different(a as u64, b as u64, tolerance);
+    assert_sufficiently_different((a >> 64) as u64, (b >> 64) as u64, tolerance);
+}
+
+fn test_finish_u128_extends_finish<T: Hasher128>(constructor: impl Fn(u128, u128) -> T) {
+    for string in ["", "1", "1234", "12345678", "1234567812345678", "12345678123456781234567812345678"].iter() {
+        let mut hasher = constructor(1, 2);
+        string.hash(&mut hasher);
+        let wide = hasher.finish_u128();
+        assert_eq!(hasher.finish(), wide as u64);
+        assert_eq!(wide, hasher.finish_u128());
+        assert_sufficiently_different(wide as u64, (wide >> 64) as u64, 2);
+    }
+}
+
+fn test_single_bit_flip_u128<T: Hasher128>(hasher: impl Fn() -> T) {
+    let compare_value = hash_u128(&0u64, &hasher);
+    for pos in 0..64 {
+        let test_value = hash_u128(&(1u64 << pos), &hasher);
+        assert_sufficiently_different_u128(compare_value, test_value, 2);
+    }
+    let compare_value = hash_u128(&0u128, &hasher);
+    for pos in 0..128 {
+        let test_value = hash_u128(&(1u128 << pos), &hasher);
+        assert_sufficiently_different_u128(compare_value, test_value, 2);
+    }
+    let compare_value = hash_u128(&[0u8; 40][..], &hasher);
+    for pos in 0..320 {
+        let mut item = [0u8; 40];
+        item[pos / 8] = 1 << (pos % 8);
+        let test_value = hash_u128(&item[..], &hasher);
+        assert_sufficiently_different_u128(compare_value, test_value, 2);
+    }
+}
+
+fn test_keys_change_output_u128<T: Hasher128>(constructor: impl Fn(u128, u128) -> T) {
+    let results: Vec<u128> = [(1, 1), (1, 2), (2, 1), (2, 2)]
+        .iter()
+        .map(|&(k1, k2)| {
+            let mut hasher = constructor(k1, k2);
+            "test".hash(&mut hasher);
+            hasher.finish_u128()
+        })
+        .collect();
+    for i in 0..results.len() {
+        for j in 0..i {
+            assert_sufficiently_different_u128(results[i], results[j], 1);
+        }
+    }
+}
+
 #[cfg(test)]
 mod fallback_tests {
     use crate::fallback_hash::*;
@@ -408,6 +471,27 @@ mod fallback_tests {
     fn fallback_length_extension() {
         test_length_extension(|a, b| AHasher::new_with_keys(a, b));
     }
+
+    impl Hasher128 for AHasher {
+        fn finish_u128(&self) -> u128 {
+            AHasher::finish_u128(self)
+        }
+    }
+
+    #[test]
+    fn fallback_finish_u128_extends_finish() {
+        test_finish_u128_extends_finish(AHasher::new_with_keys);
+    }
+
+    #[test]
+    fn fallback_single_bit_flip_u128() {
+        test_single_bit_flip_u128(|| AHasher::new_with_keys(0, 0));
+    }
+
+    #[test]
+    fn fallback_keys_change_output_u128() {
+        test_keys_change_output_u128(AHasher::new_with_keys);
+    }
 }
 
 ///Basic sanity tests of the cypto properties of aHash.
@@ -501,4 +585,27 @@ mod aes_tests {
     fn aes_length_extension() {
         test_length_extension(|a, b| AHasher::test_with_keys(a, b));
     }
+
+    impl Hasher128 for AHasher {
+        fn finish_u128(&self) -> u128 {
+            AHasher::finish_u128(self)
+        }
+    }
+
+    #[test]
+    fn aes_finish_u128_extends_finish() {
+        test_finish_u128_extends_finish(AHasher::test_with_keys);
+    }
+
+    #[test]
+    fn aes_single_bit_flip_u128() {
+        test_single_bit_flip_u128(|| AHasher::test_with_keys(BAD_KEY, BAD_KEY));
+        test_single_bit_flip_u128(|| AHasher::test_with_keys(BAD_KEY2, BAD_KEY2));
+    }
+
+    #[test]
+    fn aes_keys_change_output_u128() {
+        // Unlike `test_with_keys`, this mixes in the same constants as real keys, as the fallback test does.
+        test_keys_change_output_u128(AHasher::new_with_keys);
+    }
 }
@@ -2,7 +2,7 @@
 use crate::RandomState;
 use std::collections::{hash_set, HashSet};
 use std::fmt::{self, Debug};
-use std::hash::{BuildHasher, Hash};
+use std::hash::{BuildHasher, Hash, Hasher};
 use std::iter::FromIterator;
 use std::ops::{BitAnd, BitOr, BitXor, Deref, DerefMut, Sub};
 
@@ -104,6 +104,21 @@ where
 {
 }
 
+/// Hashes the items in a way that does not depend on their order, so that sets which are equal hash the same even if
+/// they have different hashers. (See [MultisetHash](crate::MultisetHash)) The keys used to hash each item are derived
+/// from the hasher that the set is written to, so they are as secret as its keys.
+impl<T, S> Hash for AHashSet<T, S>
+where
+    T: Eq + Hash,
+    S: BuildHasher,
+{
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        let mut items = crate::MultisetHash::keyed_by(state);
+        items.extend(self.0.iter());
+        items.write_to(state);
+    }
+}
+
 impl<T, S> BitOr<&AHashSet<T, S>> for &AHashSet<T, S>
 where
     T: Eq + Hash + Clone,
@@ -331,10 +346,59 @@ where
     }
 }
 
-#[cfg(all(test, feature = "serde"))]
+#[cfg(test)]
 mod test {
     use super::*;
 
+    #[test]
+    fn test_hash_is_order_independent() {
+        let a: AHashSet<&str> = ["x", "y", "z"].into();
+        let mut b = AHashSet::with_hasher(RandomState::with_seeds(1, 2, 3, 4));
+        b.insert("z");
+        b.insert("y");
+        b.insert("x");
+        assert_eq!(a, b);
+        let build_hasher = RandomState::with_seeds(5, 6, 7, 8);
+        assert_eq!(build_hasher.hash_one(&a), build_hasher.hash_one(&b));
+        b.remove("y");
+        assert_ne!(build_hasher.hash_one(&a), build_hasher.hash_one(&b));
+
+        let mut tagged = AHashSet::new();
+        tagged.insert(a.clone());
+        assert!(tagged.contains(&b.iter().chain(["y"].iter()).copied().collect::<AHashSet<_>>()));
+    }
+
+    #[test]
+    fn test_item_keys_come_from_hasher() {
+        /// Returns `key` from `finish`, and records the sum of the item hashes.
+        struct SumRecorder {
+            key: u64,
+            sum: u128,
+        }
+
+        impl Hasher for SumRecorder {
+            fn finish(&self) -> u64 {
+                self.key
+            }
+
+            fn write(&mut self, _bytes: &[u8]) {}
+
+            fn write_u128(&mut self, i: u128) {
+                self.sum = i;
+            }
+        }
+
+        let set: AHashSet<&str> = ["x", "y", "z"].into();
+        let sum = |key| {
+            let mut hasher = SumRecorder { key, sum: 0 };
+            set.hash(&mut hasher);
+            hasher.sum
+        };
+        assert_eq!(sum(1), sum(1));
+        assert_ne!(sum(1), sum(2));
+    }
+
+    #[cfg(feature = "serde")]
     #[test]
     fn test_serde() {
         let mut set = AHashSet::new();
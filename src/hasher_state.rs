@@ -0,0 +1,150 @@
+//! The serialized form of a partially computed [AHasher](crate::AHasher). (See `AHasher::export_state`)
+//!
+//! The layout is a version byte, followed by a byte identifying the algorithm, followed by the version of aHash which
+//! exported it as 3 little-endian `u16`s, followed by the algorithm's fields as 3 little-endian `u128`s.
+//!
+//! The output of the hashers may change in any release, so a state is only accepted by exactly the same version of
+//! aHash that exported it.
+use core::fmt;
+
+/// The number of bytes before the fields.
+const HEADER_LEN: usize = 2 + 3 * 2;
+
+/// The number of bytes returned by `AHasher::export_state`.
+pub(crate) const STATE_LEN: usize = HEADER_LEN + 3 * 16;
+
+/// The version of the layout.
+const VERSION: u8 = 2;
+
+/// The version of aHash, which identifies the algorithms used.
+const CRATE_VERSION: [u16; 3] = [
+    parse_version(env!("CARGO_PKG_VERSION_MAJOR")),
+    parse_version(env!("CARGO_PKG_VERSION_MINOR")),
+    parse_version(env!("CARGO_PKG_VERSION_PATCH")),
+];
+
+const fn parse_version(digits: &str) -> u16 {
+    let digits = digits.as_bytes();
+    let mut result = 0;
+    let mut i = 0;
+    while i < digits.len() {
+        result = result * 10 + (digits[i] - b'0') as u16;
+        i += 1;
+    }
+    result
+}
+
+/// The fallback algorithm using a 128 bit multiply.
+#[allow(dead_code)] // Depends on the backend
+pub(crate) const FALLBACK_FOLDED: u8 = 1;
+/// The fallback algorithm on platforms without a 128 bit multiply.
+#[allow(dead_code)] // Depends on the backend
+pub(crate) const FALLBACK_WRAPPING: u8 = 2;
+/// The AES based algorithm.
+#[allow(dead_code)] // Depends on the backend
+pub(crate) const AES: u8 = 3;
+
+/// The reason a state passed to `AHasher::import_state` was rejected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ImportStateError {
+    /// The state was not the length produced by `export_state`.
+    InvalidLength,
+    /// The state was exported by a different version of aHash, which may use a different format or algorithm.
+    UnsupportedVersion,
+    /// The state was exported by a different algorithm than the one used by this hasher. (For example, if it was
+    /// exported on a CPU with AES instructions and imported on one without)
+    BackendMismatch,
+}
+
+impl fmt::Display for ImportStateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ImportStateError::InvalidLength => f.write_str("hasher state has the wrong length"),
+            ImportStateError::UnsupportedVersion => f.write_str("hasher state has an unsupported version"),
+            ImportStateError::BackendMismatch => f.write_str("hasher state was exported by a different algorithm"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ImportStateError {}
+
+#[inline]
+#[allow(dead_code)] // Depends on the backend
+pub(crate) fn encode(backend: u8, fields: [u128; 3]) -> [u8; STATE_LEN] {
+    let mut result = [0; STATE_LEN];
+    result[0] = VERSION;
+    result[1] = backend;
+    for (i, part) in CRATE_VERSION.iter().enumerate() {
+        result[2 + 2 * i..2 + 2 * (i + 1)].copy_from_slice(&part.to_le_bytes());
+    }
+    for (i, field) in fields.iter().enumerate() {
+        result[HEADER_LEN + 16 * i..HEADER_LEN + 16 * (i + 1)].copy_from_slice(&field.to_le_bytes());
+    }
+    result
+}
+
+#[inline]
+#[allow(dead_code)] // Depends on the backend
+pub(crate) fn decode(backend: u8, state: &[u8]) -> Result<[u128; 3], ImportStateError> {
+    if state.len() != STATE_LEN {
+        return Err(ImportStateError::InvalidLength);
+    }
+    if state[0] != VERSION {
+        return Err(ImportStateError::UnsupportedVersion);
+    }
+    for (i, part) in CRATE_VERSION.iter().enumerate() {
+        if state[2 + 2 * i..2 + 2 * (i + 1)] != part.to_le_bytes() {
+            return Err(ImportStateError::UnsupportedVersion);
+        }
+    }
+    if state[1] != backend {
+        return Err(ImportStateError::BackendMismatch);
+    }
+    let mut fields = [0; 3];
+    for (i, field) in fields.iter_mut().enumerate() {
+        let mut bytes = [0; 16];
+        bytes.copy_from_slice(&state[HEADER_LEN + 16 * i..HEADER_LEN + 16 * (i + 1)]);
+        *field = u128::from_le_bytes(bytes);
+    }
+    Ok(fields)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let fields = [1, u128::MAX, 0x0123_4567_89ab_cdef_fedc_ba98_7654_3210];
+        let state = encode(AES, fields);
+        assert_eq!(state[..2], [VERSION, AES]);
+        assert_eq!(Ok(fields), decode(AES, &state));
+    }
+
+    #[test]
+    fn test_rejects_invalid() {
+        let state = encode(FALLBACK_FOLDED, [1, 2, 0]);
+        assert_eq!(Err(ImportStateError::BackendMismatch), decode(AES, &state));
+        assert_eq!(Err(ImportStateError::InvalidLength), decode(FALLBACK_FOLDED, &state[1..]));
+        assert_eq!(Err(ImportStateError::InvalidLength), decode(FALLBACK_FOLDED, &[]));
+        let mut newer = state;
+        newer[0] = VERSION + 1;
+        assert_eq!(Err(ImportStateError::UnsupportedVersion), decode(FALLBACK_FOLDED, &newer));
+    }
+
+    #[test]
+    fn test_rejects_other_crate_version() {
+        assert_eq!(
+            env!("CARGO_PKG_VERSION"),
+            format!("{}.{}.{}", CRATE_VERSION[0], CRATE_VERSION[1], CRATE_VERSION[2])
+        );
+        let state = encode(AES, [1, 2, 3]);
+        for byte in 2..HEADER_LEN {
+            let mut other = state;
+            other[byte] ^= 1;
+            assert_eq!(Err(ImportStateError::UnsupportedVersion), decode(AES, &other));
+        }
+    }
+}
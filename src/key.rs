@@ -0,0 +1,318 @@
+//! Optimized hashing of common key types which does not depend on specialization.
+use crate::RandomState;
+use core::fmt;
+use core::hash::{BuildHasher, Hash, Hasher};
+use core::marker::PhantomData;
+
+#[cfg(not(feature = "std"))]
+extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std as alloc;
+
+use alloc::string::String;
+use alloc::vec::Vec;
+
+mod private {
+    pub trait Sealed {}
+}
+
+/// A key type which has an optimized hash function.
+///
+/// When specialization is available (on nightly) [RandomState] automatically uses shorter hash functions for
+/// primitives and strings. This trait provides the same speedup on stable Rust: [KeyRandomState] selects the optimized
+/// function based on the type of the key, so a map using it hashes keys the way [RandomState::hash_u64] and
+/// [RandomState::hash_str] do.
+///
+/// It is implemented for the primitive integers, `str`, `String`, `[u8]`, `Vec<u8>` and references to them. It is
+/// sealed so that it cannot be implemented for other types.
+pub trait AHashKey: Hash + Eq + private::Sealed {
+    #[doc(hidden)]
+    type Hasher: Hasher;
+
+    #[doc(hidden)]
+    fn key_hasher(state: &RandomState) -> Self::Hasher;
+}
+
+/// Hashes an integer of 64 bits or less.
+#[doc(hidden)]
+pub struct IntKeyHasher {
+    state: RandomState,
+    hash: u64,
+}
+
+/// Hashes a fixed size integer larger than 64 bits, or a `usize`.
+#[doc(hidden)]
+pub struct FixedKeyHasher {
+    state: RandomState,
+    hash: u64,
+}
+
+/// Hashes a single string or slice of bytes.
+#[doc(hidden)]
+pub struct StrKeyHasher {
+    state: RandomState,
+    hash: u64,
+}
+
+// The key types write a single value, so each of these hashers computes the whole hash when that value is written.
+
+impl Hasher for IntKeyHasher {
+    #[inline]
+    fn finish(&self) -> u64 {
+        self.hash
+    }
+
+    #[inline]
+    fn write(&mut self, _bytes: &[u8]) {
+        unreachable!("Specialized hasher was called with a different type of object")
+    }
+
+    #[inline]
+    fn write_u8(&mut self, i: u8) {
+        self.write_u64(i as u64);
+    }
+
+    #[inline]
+    fn write_u16(&mut self, i: u16) {
+        self.write_u64(i as u64);
+    }
+
+    #[inline]
+    fn write_u32(&mut self, i: u32) {
+        self.write_u64(i as u64);
+    }
+
+    #[inline]
+    fn write_u64(&mut self, i: u64) {
+        self.hash = self.state.hash_as_u64(&i);
+    }
+}
+
+impl Hasher for FixedKeyHasher {
+    #[inline]
+    fn finish(&self) -> u64 {
+        self.hash
+    }
+
+    #[inline]
+    fn write(&mut self, _bytes: &[u8]) {
+        unreachable!("Specialized hasher was called with a different type of object")
+    }
+
+    #[inline]
+    fn write_u128(&mut self, i: u128) {
+        self.hash = self.state.hash_as_fixed_length(&i);
+    }
+
+    #[inline]
+    fn write_usize(&mut self, i: usize) {
+        self.hash = self.state.hash_as_fixed_length(&i);
+    }
+}
+
+/// Note that the calls other than `write` are ignored, because the hash impls for strings and slices tack on an unneeded
+/// call.
+impl Hasher for StrKeyHasher {
+    #[inline]
+    fn finish(&self) -> u64 {
+        self.hash
+    }
+
+    #[inline]
+    fn write(&mut self, bytes: &[u8]) {
+        self.hash = self.state.hash_as_str(bytes);
+    }
+
+    #[inline]
+    fn write_u8(&mut self, _i: u8) {}
+
+    #[inline]
+    fn write_u16(&mut self, _i: u16) {}
+
+    #[inline]
+    fn write_u32(&mut self, _i: u32) {}
+
+    #[inline]
+    fn write_u64(&mut self, _i: u64) {}
+
+    #[inline]
+    fn write_u128(&mut self, _i: u128) {}
+
+    #[inline]
+    fn write_usize(&mut self, _i: usize) {}
+}
+
+macro_rules! key_impl {
+    ($hasher:ident, $($typ:ty),*) => {
+        $(
+            impl private::Sealed for $typ {}
+
+            impl AHashKey for $typ {
+                type Hasher = $hasher;
+
+                #[inline]
+                fn key_hasher(state: &RandomState) -> $hasher {
+                    $hasher {
+                        state: state.clone(),
+                        hash: 0,
+                    }
+                }
+            }
+        )*
+    };
+}
+key_impl!(IntKeyHasher, u8, u16, u32, u64, i8, i16, i32, i64);
+key_impl!(FixedKeyHasher, u128, i128, usize, isize);
+key_impl!(StrKeyHasher, str, String, [u8], Vec<u8>);
+
+impl<T: private::Sealed + ?Sized> private::Sealed for &T {}
+
+/// References are hashed the same as the value they refer to.
+impl<T: AHashKey + ?Sized> AHashKey for &T {
+    type Hasher = T::Hasher;
+
+    #[inline]
+    fn key_hasher(state: &RandomState) -> T::Hasher {
+        T::key_hasher(state)
+    }
+}
+
+/// A [BuildHasher] for maps with keys of type `K` which uses the optimized hash function for that type. (See
+/// [AHashKey])
+///
+/// This provides the speedups that [RandomState] gets from specialization on stable Rust. The hashes are the same as
+/// those of [RandomState::hash_u64], [RandomState::hash_str] and [RandomState::hash_bytes] with the same keys.
+#[cfg_attr(
+    feature = "std",
+    doc = r##" # Examples
+```
+use ahash::{KeyHashMap, HashMapExt};
+
+let mut map: KeyHashMap<u64, &str> = KeyHashMap::new();
+map.insert(1, "one");
+assert_eq!(map.get(&1), Some(&"one"));
+```
+"##
+)]
+pub struct KeyRandomState<K: ?Sized> {
+    state: RandomState,
+    _key: PhantomData<fn(&K)>,
+}
+
+impl<K: AHashKey + ?Sized> KeyRandomState<K> {
+    /// Creates a new state using random keys. (See [RandomState::new])
+    #[cfg(any(feature = "compile-time-rng", feature = "runtime-rng", feature = "no-rng"))]
+    #[inline]
+    pub fn new() -> KeyRandomState<K> {
+        KeyRandomState::with_state(RandomState::new())
+    }
+
+    /// Creates a state which uses the keys of the provided [RandomState].
+    #[inline]
+    pub fn with_state(state: RandomState) -> KeyRandomState<K> {
+        KeyRandomState {
+            state,
+            _key: PhantomData,
+        }
+    }
+
+    /// Returns the [RandomState] that provides the keys.
+    #[inline]
+    pub fn state(&self) -> &RandomState {
+        &self.state
+    }
+}
+
+impl<K: ?Sized> Clone for KeyRandomState<K> {
+    #[inline]
+    fn clone(&self) -> Self {
+        KeyRandomState {
+            state: self.state.clone(),
+            _key: PhantomData,
+        }
+    }
+}
+
+impl<K: ?Sized> fmt::Debug for KeyRandomState<K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad("KeyRandomState { .. }")
+    }
+}
+
+/// Creates a state with random keys. This is the same as [KeyRandomState::new()]
+#[cfg(any(feature = "compile-time-rng", feature = "runtime-rng", feature = "no-rng"))]
+impl<K: AHashKey + ?Sized> Default for KeyRandomState<K> {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: AHashKey + ?Sized> From<RandomState> for KeyRandomState<K> {
+    #[inline]
+    fn from(state: RandomState) -> Self {
+        Self::with_state(state)
+    }
+}
+
+impl<K: AHashKey + ?Sized> BuildHasher for KeyRandomState<K> {
+    type Hasher = K::Hasher;
+
+    #[inline]
+    fn build_hasher(&self) -> K::Hasher {
+        K::key_hasher(&self.state)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn key_hash<K: AHashKey + ?Sized>(state: &RandomState, key: &K) -> u64 {
+        KeyRandomState::<K>::with_state(state.clone()).hash_one(key)
+    }
+
+    #[test]
+    fn test_matches_explicit_methods() {
+        let state = RandomState::with_seeds(1, 2, 3, 4);
+        assert_eq!(key_hash(&state, &1234_u64), state.hash_u64(1234));
+        assert_eq!(key_hash(&state, &7_u8), state.hash_u64(7));
+        assert_eq!(key_hash(&state, &-1_i32), state.hash_u64(u32::MAX as u64));
+        assert_eq!(key_hash(&state, "hello"), state.hash_str("hello"));
+        assert_eq!(key_hash(&state, &String::from("hello")), state.hash_str("hello"));
+        assert_eq!(key_hash(&state, &&"hello"), state.hash_str("hello"));
+        assert_eq!(key_hash(&state, &b"hello"[..]), state.hash_bytes(b"hello"));
+        assert_eq!(key_hash(&state, &b"hello".to_vec()), state.hash_bytes(b"hello"));
+        assert_eq!(state.hash_str("a longer string of text"), state.hash_bytes(b"a longer string of text"));
+        assert_eq!(key_hash(&state, &5_u128), key_hash(&state, &5_i128));
+    }
+
+    #[test]
+    fn test_distinct() {
+        let state = RandomState::with_seeds(1, 2, 3, 4);
+        assert_ne!(state.hash_u64(1), state.hash_u64(2));
+        assert_ne!(state.hash_u64(1), RandomState::with_seeds(2, 2, 3, 4).hash_u64(1));
+        assert_ne!(state.hash_str("a"), state.hash_str("b"));
+        assert_ne!(state.hash_str(""), state.hash_str("\0"));
+        assert_ne!(key_hash(&state, &1_usize), key_hash(&state, &2_usize));
+        assert_ne!(key_hash(&state, &1_u128), key_hash(&state, &(1_u128 << 64)));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_maps() {
+        use crate::{HashMapExt, HashSetExt, KeyHashMap, KeyHashSet};
+
+        let mut map: KeyHashMap<String, usize> = KeyHashMap::new();
+        for i in 0..1000 {
+            map.insert(i.to_string(), i);
+        }
+        for i in 0..1000 {
+            assert_eq!(map.get(i.to_string().as_str()), Some(&i));
+        }
+        let mut set: KeyHashSet<u128> = KeyHashSet::with_capacity(10);
+        set.insert(1 << 100);
+        assert!(set.contains(&(1 << 100)));
+        assert!(!set.contains(&1));
+    }
+}
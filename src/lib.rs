@@ -9,10 +9,13 @@
 //!
 //! When it is available aHash uses the hardware AES instructions to provide a keyed hash function.
 //! When it is not, aHash falls back on a slightly slower alternative algorithm.
+//! Normally this choice is made at compile time based on the target features. If the `runtime-dispatch` feature is
+//! enabled on x86, the AES instructions are instead detected when the program runs.
 //!
 //! Because aHash does not have a fixed standard for its output, it is able to improve over time.
 //! But this also means that different computers or computers using different versions of ahash may observe different
-//! hash values for the same input.
+//! hash values for the same input. Where the output must not change, such as for values which are persisted or sent
+//! between machines, the versioned hashers in the [stable] module can be used instead.
 #![cfg_attr(
     all(feature = "std", any(feature = "compile-time-rng", feature = "runtime-rng", feature = "no-rng")),
     doc = r##"
@@ -102,23 +105,111 @@ Note the import of [HashMapExt]. This is needed for the constructor.
 #[macro_use]
 mod convert;
 
+/// Implements [Default] for a hasher, using the fixed keys.
+macro_rules! impl_default_hasher {
+    ($hasher:ty) => {
+        /// Provides a default [Hasher] with fixed keys.
+        /// This is typically used in conjunction with [BuildHasherDefault] to create
+        /// [AHasher]s in order to hash the keys of the map.
+        ///
+        /// Generally it is preferable to use [RandomState] instead, so that different
+        /// hashmaps will have different keys. However if fixed keys are desirable this
+        /// may be used instead.
+        ///
+        /// # Example
+        /// ```
+        /// use std::hash::BuildHasherDefault;
+        /// use ahash::{AHasher, RandomState};
+        /// use std::collections::HashMap;
+        ///
+        /// let mut map: HashMap<i32, i32, BuildHasherDefault<AHasher>> = HashMap::default();
+        /// map.insert(12, 34);
+        /// ```
+        ///
+        /// [BuildHasherDefault]: std::hash::BuildHasherDefault
+        /// [Hasher]: std::hash::Hasher
+        /// [HashMap]: std::collections::HashMap
+        impl Default for $hasher {
+            /// Constructs a new [AHasher] with fixed keys.
+            /// If `std` is enabled these will be generated upon first invocation.
+            /// Otherwise if the `compile-time-rng`feature is enabled these will be generated at compile time.
+            /// If neither of these features are available, hardcoded constants will be used.
+            ///
+            /// Because the values are fixed, different hashers will all hash elements the same way.
+            /// This could make hash values predictable, if DOS attacks are a concern. If this behaviour is
+            /// not required, it may be preferable to use [RandomState] instead.
+            ///
+            /// # Examples
+            ///
+            /// ```
+            /// use ahash::AHasher;
+            /// use std::hash::Hasher;
+            ///
+            /// let mut hasher_1 = AHasher::default();
+            /// let mut hasher_2 = AHasher::default();
+            ///
+            /// hasher_1.write_u32(1234);
+            /// hasher_2.write_u32(1234);
+            ///
+            /// assert_eq!(hasher_1.finish(), hasher_2.finish());
+            /// ```
+            #[inline]
+            fn default() -> $hasher {
+                <$hasher>::from_random_state(&RandomState::with_fixed_keys())
+            }
+        }
+    };
+}
+
+mod aes_hash;
 mod fallback_hash;
 
+impl_default_hasher!(aes_hash::AHasher);
+impl_default_hasher!(fallback_hash::AHasher);
+
 cfg_if::cfg_if! {
-    if #[cfg(any(
+    if #[cfg(feature = "force-fallback")] {
+        pub use crate::fallback_hash::AHasher;
+    } else if #[cfg(any(
             all(any(target_arch = "x86", target_arch = "x86_64"), target_feature = "aes", not(miri)),
             all(any(target_arch = "arm", target_arch = "aarch64"),
                 any(target_feature = "aes", target_feature = "crypto"),
                 not(miri),
                 feature = "stdsimd")
             ))] {
-        mod aes_hash;
         pub use crate::aes_hash::AHasher;
+    } else if #[cfg(all(feature = "runtime-dispatch", any(target_arch = "x86", target_arch = "x86_64"), not(miri)))] {
+        mod dispatch_hash;
+        pub use crate::dispatch_hash::{AHasher, AesHasher};
+
+        impl_default_hasher!(dispatch_hash::AHasher);
+        impl_default_hasher!(dispatch_hash::AesHasher);
     } else {
         pub use crate::fallback_hash::AHasher;
     }
 }
 
+/// The hasher using the fallback algorithm, which does not need AES instructions.
+///
+/// [AHasher] is the best algorithm available, so it is usually the same as [AesHasher] if the target supports AES
+/// instructions and the same as this otherwise. This type can be used to always select this algorithm, for example to
+/// compare the two. (The `force-fallback` feature makes [AHasher] use it as well)
+pub use crate::fallback_hash::AHasher as FallbackHasher;
+
+/// The hasher using the AES based algorithm.
+///
+/// If the target is not compiled with AES instructions this uses a software implementation of the AES round, which
+/// produces the same hashes but is much slower. (So it should generally not be used in that case except to compare
+/// the output) With the `runtime-dispatch` feature the instructions are used if the CPU supports them.
+#[cfg(not(all(
+    feature = "runtime-dispatch",
+    not(feature = "force-fallback"),
+    any(target_arch = "x86", target_arch = "x86_64"),
+    not(target_feature = "aes"),
+    not(miri)
+)))]
+pub use crate::aes_hash::AHasher as AesHasher;
+
 cfg_if::cfg_if! {
     if #[cfg(feature = "std")] {
         mod hash_map;
@@ -134,17 +225,52 @@ cfg_if::cfg_if! {
 
         /// Type alias for [HashSet]<K, ahash::RandomState>
         pub type HashSet<K> = std::collections::HashSet<K, crate::RandomState>;
+
+        /// Type alias for [HashMap]<K, V, ahash::KeyRandomState<K>>, which hashes keys with the optimized
+        /// function for their type. (See [AHashKey])
+        pub type KeyHashMap<K, V> = std::collections::HashMap<K, V, crate::KeyRandomState<K>>;
+
+        /// Type alias for [HashSet]<K, ahash::KeyRandomState<K>>, which hashes keys with the optimized
+        /// function for their type. (See [AHashKey])
+        pub type KeyHashSet<K> = std::collections::HashSet<K, crate::KeyRandomState<K>>;
     }
 }
 
+mod backend;
+mod config;
+#[cfg(feature = "digest")]
+mod hash_digest;
 #[cfg(test)]
 mod hash_quality_test;
 
+mod hasher_state;
+mod key;
+mod multiset;
 mod operations;
+#[cfg(feature = "rayon")]
+mod parallel;
 pub mod random_state;
+#[cfg(feature = "serde")]
+pub mod serde_hash;
+mod soft_aes;
 mod specialize;
-
-pub use crate::random_state::RandomState;
+pub mod stable;
+mod stream;
+#[cfg(feature = "testing")]
+pub mod testing;
+
+#[cfg(feature = "digest")]
+pub use crate::hash_digest::{AHashDigest128, AHashDigest64};
+pub use crate::backend::AesRandomState;
+pub use crate::backend::FallbackRandomState;
+pub use crate::config::{config, Backend, Config, SeedSource};
+pub use crate::hasher_state::ImportStateError;
+pub use crate::key::{AHashKey, KeyRandomState};
+pub use crate::multiset::MultisetHash;
+pub use crate::random_state::{RandomState, SeedError};
+#[cfg(feature = "runtime-rng")]
+pub use crate::random_state::reseed_after_fork;
+pub use crate::stream::AHashStream;
 
 use core::hash::BuildHasher;
 use core::hash::Hash;
@@ -198,57 +324,6 @@ where
     }
 }
 
-/// Provides a default [Hasher] with fixed keys.
-/// This is typically used in conjunction with [BuildHasherDefault] to create
-/// [AHasher]s in order to hash the keys of the map.
-///
-/// Generally it is preferable to use [RandomState] instead, so that different
-/// hashmaps will have different keys. However if fixed keys are desirable this
-/// may be used instead.
-///
-/// # Example
-/// ```
-/// use std::hash::BuildHasherDefault;
-/// use ahash::{AHasher, RandomState};
-/// use std::collections::HashMap;
-///
-/// let mut map: HashMap<i32, i32, BuildHasherDefault<AHasher>> = HashMap::default();
-/// map.insert(12, 34);
-/// ```
-///
-/// [BuildHasherDefault]: std::hash::BuildHasherDefault
-/// [Hasher]: std::hash::Hasher
-/// [HashMap]: std::collections::HashMap
-impl Default for AHasher {
-    /// Constructs a new [AHasher] with fixed keys.
-    /// If `std` is enabled these will be generated upon first invocation.
-    /// Otherwise if the `compile-time-rng`feature is enabled these will be generated at compile time.
-    /// If neither of these features are available, hardcoded constants will be used.
-    ///
-    /// Because the values are fixed, different hashers will all hash elements the same way.
-    /// This could make hash values predictable, if DOS attacks are a concern. If this behaviour is
-    /// not required, it may be preferable to use [RandomState] instead.
-    ///
-    /// # Examples
-    ///
-    /// ```
-    /// use ahash::AHasher;
-    /// use std::hash::Hasher;
-    ///
-    /// let mut hasher_1 = AHasher::default();
-    /// let mut hasher_2 = AHasher::default();
-    ///
-    /// hasher_1.write_u32(1234);
-    /// hasher_2.write_u32(1234);
-    ///
-    /// assert_eq!(hasher_1.finish(), hasher_2.finish());
-    /// ```
-    #[inline]
-    fn default() -> AHasher {
-        RandomState::with_fixed_keys().build_hasher()
-    }
-}
-
 /// Used for specialization. (Sealed)
 pub(crate) trait BuildHasherExt: BuildHasher {
     #[doc(hidden)]
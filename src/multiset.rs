@@ -0,0 +1,195 @@
+use crate::RandomState;
+use core::fmt;
+use core::hash::{BuildHasher, Hash, Hasher};
+
+/// An order independent hash of a multiset of values, which can be updated as values are added and removed.
+///
+/// Each value is hashed on its own with a keyed 128 bit hash, and the results are combined with wrapping addition.
+/// Because addition is commutative the result does not depend on the order in which values are inserted, and because
+/// it can be undone a value can be removed again in constant time without rehashing the rest. Inserting a value twice
+/// counts it twice, so `{a, a}` and `{a}` hash differently.
+///
+/// This is what the [Hash] implementations of [AHashMap](crate::AHashMap) and [AHashSet](crate::AHashSet) use. It is
+/// useful to maintain the hash of a large collection which changes over time.
+///
+/// The combined hash is only as unpredictable as the keys. Because anyone who knows the hashes of individual values
+/// could find different multisets with the same sum, the keys should be secret if the inputs are untrusted.
+///
+/// # Example
+/// ```
+/// use ahash::{MultisetHash, RandomState};
+///
+/// let state = RandomState::with_seed(42);
+/// let mut a = MultisetHash::new(&state);
+/// a.insert("x");
+/// a.insert("y");
+/// a.insert("z");
+/// a.remove("y");
+///
+/// let mut b = MultisetHash::new(&state);
+/// b.insert("z");
+/// b.insert("x");
+/// assert_eq!(a.finish(), b.finish());
+/// ```
+#[derive(Clone)]
+pub struct MultisetHash {
+    state: RandomState,
+    sum: u128,
+    len: u64,
+}
+
+impl MultisetHash {
+    /// Creates an empty multiset hash using the keys of the provided [RandomState].
+    #[inline]
+    pub fn new(state: &RandomState) -> MultisetHash {
+        MultisetHash {
+            state: state.clone(),
+            sum: 0,
+            len: 0,
+        }
+    }
+
+    /// Adds a value to the multiset.
+    #[inline]
+    pub fn insert<T: Hash + ?Sized>(&mut self, value: &T) {
+        self.sum = self.sum.wrapping_add(self.state.hash_one_u128(value));
+        self.len = self.len.wrapping_add(1);
+    }
+
+    /// Removes a value which was previously inserted.
+    ///
+    /// Removing a value that was not inserted will not panic, but the result will no longer match the hash of any
+    /// multiset.
+    #[inline]
+    pub fn remove<T: Hash + ?Sized>(&mut self, value: &T) {
+        self.sum = self.sum.wrapping_sub(self.state.hash_one_u128(value));
+        self.len = self.len.wrapping_sub(1);
+    }
+
+    /// The number of values in the multiset. (Inserts minus removes)
+    #[inline]
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    /// Returns true if the multiset is empty.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the hash of the multiset.
+    #[inline]
+    pub fn finish(&self) -> u64 {
+        self.finish_hasher().finish()
+    }
+
+    /// Returns a 128 bit hash of the multiset.
+    #[inline]
+    pub fn finish_u128(&self) -> u128 {
+        self.finish_hasher().finish_u128()
+    }
+
+    #[inline]
+    fn finish_hasher(&self) -> crate::AHasher {
+        let mut hasher = self.state.build_hasher();
+        hasher.write_u64(self.len);
+        hasher.write_u128(self.sum);
+        hasher
+    }
+
+    /// Creates an empty multiset hash with keys derived from the output of `state`, so that they are as unpredictable
+    /// as the keys of `state`. (This writes to `state` as well, but only data which is the same every time)
+    #[cfg(feature = "std")]
+    #[inline]
+    pub(crate) fn keyed_by<H: Hasher>(state: &mut H) -> MultisetHash {
+        let mut keys = [0; 4];
+        for (i, key) in keys.iter_mut().enumerate() {
+            state.write_u8(i as u8);
+            *key = state.finish();
+        }
+        MultisetHash::new(&RandomState::with_seeds(keys[0], keys[1], keys[2], keys[3]))
+    }
+
+    /// Writes the combined state to another hasher. Two multisets with the same keys and values write the same data.
+    #[cfg(feature = "std")]
+    #[inline]
+    pub(crate) fn write_to<H: Hasher>(&self, state: &mut H) {
+        state.write_u64(self.len);
+        state.write_u128(self.sum);
+    }
+}
+
+/// Creates an empty multiset hash with fixed keys. The same as those used by [AHasher::default](crate::AHasher).
+impl Default for MultisetHash {
+    #[inline]
+    fn default() -> MultisetHash {
+        MultisetHash::new(&RandomState::with_fixed_keys())
+    }
+}
+
+impl fmt::Debug for MultisetHash {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MultisetHash").field("len", &self.len).finish()
+    }
+}
+
+impl<T: Hash> Extend<T> for MultisetHash {
+    #[inline]
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for value in iter {
+            self.insert(&value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_order_independent() {
+        let state = RandomState::with_seeds(1, 2, 3, 4);
+        let values: Vec<u64> = (0..100).collect();
+        let mut forward = MultisetHash::new(&state);
+        forward.extend(values.iter());
+        let mut backward = MultisetHash::new(&state);
+        backward.extend(values.iter().rev());
+        assert_eq!(forward.finish(), backward.finish());
+        assert_eq!(forward.finish_u128(), backward.finish_u128());
+        assert_eq!(forward.len(), 100);
+    }
+
+    #[test]
+    fn test_remove() {
+        let state = RandomState::with_seeds(1, 2, 3, 4);
+        let empty = MultisetHash::new(&state);
+        let mut set = empty.clone();
+        set.insert("a");
+        set.insert("b");
+        let mut only_b = empty.clone();
+        only_b.insert("b");
+        assert_ne!(set.finish(), only_b.finish());
+        set.remove("a");
+        assert_eq!(set.finish(), only_b.finish());
+        set.remove("b");
+        assert!(set.is_empty());
+        assert_eq!(set.finish(), empty.finish());
+    }
+
+    #[test]
+    fn test_multiplicity_and_keys() {
+        let state = RandomState::with_seeds(1, 2, 3, 4);
+        let mut once = MultisetHash::new(&state);
+        once.insert(&7_u32);
+        let mut twice = once.clone();
+        twice.insert(&7_u32);
+        let mut other = MultisetHash::new(&state);
+        other.insert(&8_u32);
+        assert_ne!(once.finish(), twice.finish());
+        assert_ne!(once.finish(), other.finish());
+        let mut other_keys = MultisetHash::new(&RandomState::with_seeds(1, 2, 3, 5));
+        other_keys.insert(&7_u32);
+        assert_ne!(once.finish(), other_keys.finish());
+    }
+}
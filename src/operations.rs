@@ -98,7 +98,11 @@ pub(crate) fn add_by_64s(a: [u64; 2], b: [u64; 2]) -> [u64; 2] {
     [a[0].wrapping_add(b[0]), a[1].wrapping_add(b[1])]
 }
 
-#[cfg(all(any(target_arch = "x86", target_arch = "x86_64"), target_feature = "aes", not(miri)))]
+#[cfg(all(
+    any(target_arch = "x86", target_arch = "x86_64"),
+    any(target_feature = "aes", all(feature = "runtime-dispatch", not(feature = "force-fallback"))),
+    not(miri)
+))]
 #[allow(unused)]
 #[inline(always)]
 pub(crate) fn aesenc(value: u128, xor: u128) -> u128 {
@@ -133,7 +137,11 @@ pub(crate) fn aesenc(value: u128, xor: u128) -> u128 {
     }
 }
 
-#[cfg(all(any(target_arch = "x86", target_arch = "x86_64"), target_feature = "aes", not(miri)))]
+#[cfg(all(
+    any(target_arch = "x86", target_arch = "x86_64"),
+    any(target_feature = "aes", all(feature = "runtime-dispatch", not(feature = "force-fallback"))),
+    not(miri)
+))]
 #[allow(unused)]
 #[inline(always)]
 pub(crate) fn aesdec(value: u128, xor: u128) -> u128 {
@@ -168,6 +176,84 @@ pub(crate) fn aesdec(value: u128, xor: u128) -> u128 {
     }
 }
 
+#[cfg(not(any(
+    all(
+        any(target_arch = "x86", target_arch = "x86_64"),
+        any(target_feature = "aes", all(feature = "runtime-dispatch", not(feature = "force-fallback"))),
+        not(miri)
+    ),
+    all(
+        any(target_arch = "arm", target_arch = "aarch64"),
+        any(target_feature = "aes", target_feature = "crypto"),
+        not(miri),
+        feature = "stdsimd"
+    )
+)))]
+#[allow(unused)]
+#[inline(always)]
+pub(crate) fn aesenc(value: u128, xor: u128) -> u128 {
+    crate::soft_aes::aesenc(value, xor)
+}
+
+#[cfg(not(any(
+    all(
+        any(target_arch = "x86", target_arch = "x86_64"),
+        any(target_feature = "aes", all(feature = "runtime-dispatch", not(feature = "force-fallback"))),
+        not(miri)
+    ),
+    all(
+        any(target_arch = "arm", target_arch = "aarch64"),
+        any(target_feature = "aes", target_feature = "crypto"),
+        not(miri),
+        feature = "stdsimd"
+    )
+)))]
+#[allow(unused)]
+#[inline(always)]
+pub(crate) fn aesdec(value: u128, xor: u128) -> u128 {
+    crate::soft_aes::aesdec(value, xor)
+}
+
+/// A single round of AES encryption and decryption. The AES based hasher is generic over this so that the same code can
+/// be compiled both for the round the build targets and for the software implementation.
+pub(crate) trait AesRound {
+    fn aesenc(value: u128, xor: u128) -> u128;
+    fn aesdec(value: u128, xor: u128) -> u128;
+}
+
+/// The round of [aesenc] and [aesdec], which use the AES instructions if the build targets them.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct NativeAes;
+
+impl AesRound for NativeAes {
+    #[inline(always)]
+    fn aesenc(value: u128, xor: u128) -> u128 {
+        aesenc(value, xor)
+    }
+
+    #[inline(always)]
+    fn aesdec(value: u128, xor: u128) -> u128 {
+        aesdec(value, xor)
+    }
+}
+
+/// The software implementation of the round, which is used regardless of the CPU features.
+#[derive(Debug, Clone, Copy)]
+#[allow(dead_code)] // Only used with runtime-dispatch
+pub(crate) struct SoftAes;
+
+impl AesRound for SoftAes {
+    #[inline(always)]
+    fn aesenc(value: u128, xor: u128) -> u128 {
+        crate::soft_aes::aesenc(value, xor)
+    }
+
+    #[inline(always)]
+    fn aesdec(value: u128, xor: u128) -> u128 {
+        crate::soft_aes::aesdec(value, xor)
+    }
+}
+
 #[allow(unused)]
 #[inline(always)]
 pub(crate) fn add_in_length(enc: &mut u128, len: u64) {
@@ -192,6 +278,31 @@ pub(crate) fn add_in_length(enc: &mut u128, len: u64) {
     }
 }
 
+/// Hashes four values of type `V` in lockstep, so that the CPU can overlap their independent chains of operations.
+pub(crate) trait HashX4<V: ?Sized> {
+    fn hash_x4(&self, values: [&V; 4]) -> [u64; 4];
+}
+
+/// Hashes `values` four at a time using `kernel`, storing the hash of `values[i]` in `hashes[i]`.
+///
+/// If the number of values is not a multiple of four, the last group is padded by repeating its final value and the
+/// extra results are discarded.
+#[inline(always)]
+pub(crate) fn hash_by_4<V, K: HashX4<V>>(kernel: &K, values: &[V], hashes: &mut [u64]) {
+    assert_eq!(values.len(), hashes.len(), "values and hashes must have the same length");
+    let mut value_chunks = values.chunks_exact(4);
+    let mut hash_chunks = hashes.chunks_exact_mut(4);
+    for (value, hash) in (&mut value_chunks).zip(&mut hash_chunks) {
+        hash.copy_from_slice(&kernel.hash_x4([&value[0], &value[1], &value[2], &value[3]]));
+    }
+    let rest = value_chunks.remainder();
+    if let Some(last) = rest.last() {
+        let padded = [&rest[0], rest.get(1).unwrap_or(last), rest.get(2).unwrap_or(last), last];
+        let hash = hash_chunks.into_remainder();
+        hash.copy_from_slice(&kernel.hash_x4(padded)[..hash.len()]);
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
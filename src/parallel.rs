@@ -0,0 +1,120 @@
+//! Hashing of large buffers using multiple threads. (Requires the `rayon` feature)
+use crate::RandomState;
+use core::hash::{BuildHasher, Hasher};
+use rayon::prelude::*;
+
+/// The number of bytes hashed by each leaf of the tree.
+///
+/// This is part of the definition of the hash, so it must not depend on the number of threads or the input.
+pub(crate) const LEAF_SIZE: usize = 256 * 1024;
+
+const LEAF: u8 = 0;
+const NODE: u8 = 1;
+const ROOT: u8 = 2;
+
+#[inline]
+fn hash_leaf(state: &RandomState, index: usize, leaf: &[u8]) -> u128 {
+    let mut hasher = state.build_hasher();
+    hasher.write_u8(LEAF);
+    hasher.write_u64(index as u64);
+    hasher.write(leaf);
+    hasher.finish_u128()
+}
+
+#[inline]
+fn hash_node(state: &RandomState, left: u128, right: u128) -> u128 {
+    let mut hasher = state.build_hasher();
+    hasher.write_u8(NODE);
+    hasher.write_u128(left);
+    hasher.write_u128(right);
+    hasher.finish_u128()
+}
+
+/// Hashes `data` as a binary tree of [LEAF_SIZE] leaves.
+///
+/// The leaves are hashed in parallel, then each level of the tree is formed by combining adjacent pairs of nodes
+/// from the level below. (An odd node at the end of a level is carried up unchanged) The shape of the tree depends
+/// only on the length of the data, so the result is the same regardless of how the work is divided between threads.
+pub(crate) fn hash_large_parallel(state: &RandomState, data: &[u8]) -> u64 {
+    let mut nodes: Vec<u128> = if data.is_empty() {
+        vec![hash_leaf(state, 0, data)]
+    } else {
+        data.par_chunks(LEAF_SIZE)
+            .enumerate()
+            .map(|(index, leaf)| hash_leaf(state, index, leaf))
+            .collect()
+    };
+    while nodes.len() > 1 {
+        nodes = nodes
+            .par_chunks(2)
+            .map(|pair| match *pair {
+                [left, right] => hash_node(state, left, right),
+                [single] => single,
+                _ => unreachable!(),
+            })
+            .collect();
+    }
+    let mut hasher = state.build_hasher();
+    hasher.write_u8(ROOT);
+    hasher.write_u64(data.len() as u64);
+    hasher.write_u128(nodes[0]);
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// A single threaded implementation of the same tree.
+    fn hash_serial(state: &RandomState, data: &[u8]) -> u64 {
+        fn subtree(state: &RandomState, nodes: &[u128]) -> Vec<u128> {
+            nodes
+                .chunks(2)
+                .map(|pair| if pair.len() == 2 { hash_node(state, pair[0], pair[1]) } else { pair[0] })
+                .collect()
+        }
+        let mut nodes: Vec<u128> = data.chunks(LEAF_SIZE).enumerate().map(|(i, l)| hash_leaf(state, i, l)).collect();
+        if nodes.is_empty() {
+            nodes.push(hash_leaf(state, 0, &[]));
+        }
+        while nodes.len() > 1 {
+            nodes = subtree(state, &nodes);
+        }
+        let mut hasher = state.build_hasher();
+        hasher.write_u8(ROOT);
+        hasher.write_u64(data.len() as u64);
+        hasher.write_u128(nodes[0]);
+        hasher.finish()
+    }
+
+    #[test]
+    fn test_independent_of_thread_count() {
+        let state = RandomState::with_seeds(1, 2, 3, 4);
+        let data: Vec<u8> = (0..5 * LEAF_SIZE + 123).map(|i| (i % 251) as u8).collect();
+        for len in [0, 1, LEAF_SIZE - 1, LEAF_SIZE, LEAF_SIZE + 1, 3 * LEAF_SIZE, data.len()] {
+            let expected = hash_serial(&state, &data[..len]);
+            for threads in [1, 2, 3, 8] {
+                let pool = rayon::ThreadPoolBuilder::new().num_threads(threads).build().unwrap();
+                let result = pool.install(|| hash_large_parallel(&state, &data[..len]));
+                assert_eq!(expected, result, "len {} threads {}", len, threads);
+            }
+        }
+    }
+
+    #[test]
+    fn test_distinguishes_inputs() {
+        let state = RandomState::with_seeds(1, 2, 3, 4);
+        let mut data = vec![0_u8; 3 * LEAF_SIZE];
+        let original = hash_large_parallel(&state, &data);
+        assert_ne!(original, hash_large_parallel(&state, &data[..data.len() - 1]));
+        assert_ne!(original, hash_large_parallel(&RandomState::with_seeds(1, 2, 3, 5), &data));
+        data[2 * LEAF_SIZE + 17] = 1;
+        assert_ne!(original, hash_large_parallel(&state, &data));
+        // Swapping leaves changes the result.
+        let mut swapped = vec![1_u8; LEAF_SIZE];
+        swapped.extend_from_slice(&[2_u8; LEAF_SIZE]);
+        let mut other = vec![2_u8; LEAF_SIZE];
+        other.extend_from_slice(&[1_u8; LEAF_SIZE]);
+        assert_ne!(hash_large_parallel(&state, &swapped), hash_large_parallel(&state, &other));
+    }
+}
@@ -1,11 +1,15 @@
 
 use core::hash::Hash;
 cfg_if::cfg_if! {
-    if #[cfg(any(
+    if #[cfg(feature = "force-fallback")] {
+        use crate::fallback_hash::*;
+    } else if #[cfg(any(
         all(any(target_arch = "x86", target_arch = "x86_64"), target_feature = "aes", not(miri)),
          all(any(target_arch = "arm", target_arch = "aarch64"), any(target_feature = "aes", target_feature = "crypto"), not(miri), feature = "stdsimd")
     ))] {
         use crate::aes_hash::*;
+    } else if #[cfg(all(feature = "runtime-dispatch", any(target_arch = "x86", target_arch = "x86_64"), not(miri)))] {
+        use crate::dispatch_hash::*;
     } else {
         use crate::fallback_hash::*;
     }
@@ -30,7 +34,10 @@ use core::sync::atomic;
 
 use alloc::boxed::Box;
 use atomic::{AtomicUsize, Ordering};
-use core::any::{Any, TypeId};
+use crate::config::SeedSource;
+#[cfg(feature = "specialize")]
+use crate::operations::hash_by_4;
+use crate::operations::HashX4;
 use core::fmt;
 use core::hash::BuildHasher;
 use core::hash::Hasher;
@@ -51,8 +58,14 @@ pub(crate) const PI2: [u64; 4] = [
 
 cfg_if::cfg_if! {
     if #[cfg(all(feature = "compile-time-rng", any(test, fuzzing)))] {
+        /// Where the seeds returned by [get_default_seeds] come from. They are available without being obtained.
         #[inline]
-        fn get_fixed_seeds() -> &'static [[u64; 4]; 2] {
+        fn default_seed_source() -> SeedSource {
+            SeedSource::CompileTime
+        }
+
+        #[inline]
+        fn get_default_seeds() -> &'static [[u64; 4]; 2] {
             use const_random::const_random;
 
             const RAND: [[u64; 4]; 2] = [
@@ -70,22 +83,54 @@ cfg_if::cfg_if! {
             ];
             &RAND
         }
+
+        #[inline]
+        fn try_get_default_seeds() -> Result<&'static [[u64; 4]; 2], SeedError> {
+            Ok(get_default_seeds())
+        }
     } else if #[cfg(all(feature = "runtime-rng", not(fuzzing)))] {
+        /// Where the seeds returned by [get_default_seeds] come from, without obtaining them if that has not been done.
         #[inline]
-        fn get_fixed_seeds() -> &'static [[u64; 4]; 2] {
-            use crate::convert::Convert;
+        fn default_seed_source() -> SeedSource {
+            if SEEDS_FAILED.load(Ordering::Relaxed) {
+                SeedSource::FailurePolicy
+            } else if SEEDS.get().is_some() {
+                SeedSource::Runtime
+            } else {
+                SeedSource::NotSeeded
+            }
+        }
 
-            static SEEDS: OnceBox<[[u64; 4]; 2]> = OnceBox::new();
+        static SEEDS: OnceBox<[[u64; 4]; 2]> = OnceBox::new();
+        /// The seeds provided by the [SeedFailurePolicy] if the first attempt to obtain [SEEDS] failed. They are kept
+        /// separately so that [try_get_default_seeds] still reports the failure, and once used they stay in use for
+        /// the rest of the process so that [RandomState::with_seed] does not change.
+        static FALLBACK_SEEDS: OnceBox<[[u64; 4]; 2]> = OnceBox::new();
+
+        #[inline]
+        fn get_default_seeds() -> &'static [[u64; 4]; 2] {
+            if let Some(seeds) = FALLBACK_SEEDS.get() {
+                return seeds;
+            }
+            match SEEDS.get_or_try_init(os_seeds) {
+                Ok(seeds) => seeds,
+                Err(error) => FALLBACK_SEEDS.get_or_init(|| Box::new(seeds_after_failure(error))),
+            }
+        }
 
-            SEEDS.get_or_init(|| {
-                let mut result: [u8; 64] = [0; 64];
-                getrandom::getrandom(&mut result).expect("getrandom::getrandom() failed.");
-                Box::new(result.convert())
-            })
+        #[inline]
+        fn try_get_default_seeds() -> Result<&'static [[u64; 4]; 2], SeedError> {
+            SEEDS.get_or_try_init(os_seeds)
         }
     } else if #[cfg(feature = "compile-time-rng")] {
+        /// Where the seeds returned by [get_default_seeds] come from. They are available without being obtained.
         #[inline]
-        fn get_fixed_seeds() -> &'static [[u64; 4]; 2] {
+        fn default_seed_source() -> SeedSource {
+            SeedSource::CompileTime
+        }
+
+        #[inline]
+        fn get_default_seeds() -> &'static [[u64; 4]; 2] {
             use const_random::const_random;
 
             const RAND: [[u64; 4]; 2] = [
@@ -103,11 +148,383 @@ cfg_if::cfg_if! {
             ];
             &RAND
         }
+
+        #[inline]
+        fn try_get_default_seeds() -> Result<&'static [[u64; 4]; 2], SeedError> {
+            Ok(get_default_seeds())
+        }
     } else {
+        /// Where the seeds returned by [get_default_seeds] come from. They are available without being obtained.
+        #[inline]
+        fn default_seed_source() -> SeedSource {
+            SeedSource::Fixed
+        }
+
         #[inline]
-        fn get_fixed_seeds() -> &'static [[u64; 4]; 2] {
+        fn get_default_seeds() -> &'static [[u64; 4]; 2] {
             &[PI, PI2]
         }
+
+        #[inline]
+        fn try_get_default_seeds() -> Result<&'static [[u64; 4]; 2], SeedError> {
+            Ok(get_default_seeds())
+        }
+    }
+}
+
+#[inline]
+fn get_fixed_seeds() -> &'static [[u64; 4]; 2] {
+    #[cfg(feature = "testing")]
+    if let Some(deterministic) = crate::testing::deterministic() {
+        return deterministic.fixed_seeds();
+    }
+    get_default_seeds()
+}
+
+/// Where the seeds returned by [get_fixed_seeds] come from. (See [config](crate::config()))
+///
+/// This only reads the state, so that it cannot fail or panic. If the seeds have not been obtained yet, because no
+/// [RandomState] has been created, the source is [SeedSource::NotSeeded].
+pub(crate) fn seed_source() -> SeedSource {
+    #[cfg(feature = "testing")]
+    match crate::testing::deterministic_if_read() {
+        Some(Some(_)) => return SeedSource::Deterministic,
+        Some(None) => {}
+        // The variable is read before the seeds are obtained.
+        None => return SeedSource::NotSeeded,
+    }
+    default_seed_source()
+}
+
+/// The same as [get_fixed_seeds], but returning an error instead of applying the [SeedFailurePolicy].
+#[inline]
+fn try_get_fixed_seeds() -> Result<&'static [[u64; 4]; 2], SeedError> {
+    #[cfg(feature = "testing")]
+    if let Some(deterministic) = crate::testing::deterministic() {
+        return Ok(deterministic.fixed_seeds());
+    }
+    try_get_default_seeds()
+}
+
+#[cfg(feature = "runtime-rng")]
+fn os_seeds() -> Result<Box<[[u64; 4]; 2]>, SeedError> {
+    use crate::convert::Convert;
+
+    #[cfg(feature = "fork-safe")]
+    record_pid();
+    let mut result: [u8; 64] = [0; 64];
+    getrandom::getrandom(&mut result).map_err(|error| SeedError { error })?;
+    Ok(Box::new(result.convert()))
+}
+
+/// The error returned by [RandomState::try_new] when the operating system fails to provide random numbers.
+#[derive(Debug, Clone)]
+pub struct SeedError {
+    #[cfg(feature = "runtime-rng")]
+    error: getrandom::Error,
+}
+
+impl fmt::Display for SeedError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("failed to obtain random seeds from the operating system")?;
+        #[cfg(feature = "runtime-rng")]
+        write!(f, ": {}", self.error)?;
+        Ok(())
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for SeedError {}
+
+/// What to do if the operating system fails to provide the process wide seeds in a context which cannot return an
+/// error, such as [RandomState::new()] or [Default] for a map. (See [set_seed_failure_policy])
+///
+/// This can only happen when the `runtime-rng` feature is enabled, for example in a sandbox which blocks the system
+/// call used by `getrandom` or early during boot.
+#[derive(Debug, Clone, Copy)]
+#[non_exhaustive]
+pub enum SeedFailurePolicy {
+    /// Panic. This is the default.
+    Panic,
+    /// Use the random seeds embedded in the binary if the `compile-time-rng` feature is enabled, or fixed constants
+    /// otherwise. Note that this provides much weaker DOS resistance, or none at all in the second case.
+    FallbackSeeds,
+    /// Call the provided function to obtain the two sets of seeds.
+    Callback(fn() -> [[u64; 4]; 2]),
+}
+
+#[cfg(feature = "runtime-rng")]
+static SEED_FAILURE_POLICY: OnceBox<SeedFailurePolicy> = OnceBox::new();
+
+/// Sets the [SeedFailurePolicy] used when the operating system fails to provide the process wide seeds.
+///
+/// The policy can only be set once, and only has an effect if it is set before the seeds are first needed. (Which
+/// is when the first [RandomState] is created) If a policy was already set `Err` is returned with the provided
+/// policy.
+///
+/// This has no effect unless the `runtime-rng` feature is enabled, because no other source of seeds can fail.
+pub fn set_seed_failure_policy(policy: SeedFailurePolicy) -> Result<(), SeedFailurePolicy> {
+    cfg_if::cfg_if! {
+        if #[cfg(feature = "runtime-rng")] {
+            SEED_FAILURE_POLICY.set(Box::new(policy)).map_err(|policy| *policy)
+        } else {
+            let _ = policy;
+            Ok(())
+        }
+    }
+}
+
+/// Set when the seeds were provided by the [SeedFailurePolicy].
+#[cfg(feature = "runtime-rng")]
+static SEEDS_FAILED: atomic::AtomicBool = atomic::AtomicBool::new(false);
+
+#[cfg(feature = "runtime-rng")]
+fn seed_failure_policy() -> SeedFailurePolicy {
+    *SEED_FAILURE_POLICY.get().unwrap_or(&SeedFailurePolicy::Panic)
+}
+
+/// Applies the [SeedFailurePolicy] after the seeds could not be obtained.
+#[cfg(feature = "runtime-rng")]
+#[allow(dead_code)] // Depends on the seed source
+fn seeds_after_failure(error: SeedError) -> [[u64; 4]; 2] {
+    SEEDS_FAILED.store(true, Ordering::Relaxed);
+    apply_policy(seed_failure_policy(), error)
+}
+
+#[cfg(feature = "runtime-rng")]
+fn apply_policy(policy: SeedFailurePolicy, error: SeedError) -> [[u64; 4]; 2] {
+    match policy {
+        SeedFailurePolicy::Panic => panic!("getrandom::getrandom() failed. {}", error),
+        SeedFailurePolicy::FallbackSeeds => {
+            cfg_if::cfg_if! {
+                if #[cfg(feature = "compile-time-rng")] {
+                    use const_random::const_random;
+
+                    [
+                        [const_random!(u64), const_random!(u64), const_random!(u64), const_random!(u64)],
+                        [const_random!(u64), const_random!(u64), const_random!(u64), const_random!(u64)],
+                    ]
+                } else {
+                    [PI, PI2]
+                }
+            }
+        }
+        SeedFailurePolicy::Callback(callback) => callback(),
+    }
+}
+
+/// The number of words holding the seeds in [ForkSeeds].
+#[cfg(feature = "runtime-rng")]
+const FORK_SEED_WORDS: usize = 64 / core::mem::size_of::<usize>();
+
+/// The seeds and counter used to derive the keys of each [RandomState] after [reseed_after_fork].
+///
+/// There is one per process, which each call to [reseed_after_fork] overwrites. The seeds are stored as atomic words
+/// so that they can be replaced while other threads read them, and `generation` tells a reader if that happened.
+#[cfg(feature = "runtime-rng")]
+struct ForkSeeds {
+    /// Zero until [reseed_after_fork] is first called. It is then incremented before and after the seeds are written,
+    /// so it is odd while they are being written and changes each time they are replaced.
+    generation: AtomicUsize,
+    seeds: [AtomicUsize; FORK_SEED_WORDS],
+    counter: AtomicUsize,
+}
+
+#[cfg(feature = "runtime-rng")]
+impl ForkSeeds {
+    /// Returns the generation and the seeds, or `None` if [reseed_after_fork] has not been called.
+    fn load(&self) -> Option<(usize, [[u64; 4]; 2])> {
+        use crate::convert::Convert;
+
+        loop {
+            let generation = self.generation.load(Ordering::Acquire);
+            if generation == 0 {
+                return None;
+            }
+            let mut seeds = [0_u8; 64];
+            for (bytes, word) in seeds.chunks_exact_mut(core::mem::size_of::<usize>()).zip(self.seeds.iter()) {
+                bytes.copy_from_slice(&word.load(Ordering::Relaxed).to_ne_bytes());
+            }
+            atomic::fence(Ordering::Acquire);
+            if generation & 1 == 0 && self.generation.load(Ordering::Relaxed) == generation {
+                return Some((generation, seeds.convert()));
+            }
+            core::hint::spin_loop();
+        }
+    }
+
+    /// Replaces the seeds and restarts the counter.
+    fn store(&self, seeds: &[u8; 64]) {
+        use core::convert::TryInto;
+
+        let mut generation = self.generation.load(Ordering::Relaxed);
+        loop {
+            if generation & 1 == 0 {
+                match self.generation.compare_exchange_weak(
+                    generation,
+                    generation.wrapping_add(1),
+                    Ordering::Acquire,
+                    Ordering::Relaxed,
+                ) {
+                    Ok(_) => break,
+                    Err(current) => generation = current,
+                }
+            } else {
+                core::hint::spin_loop();
+                generation = self.generation.load(Ordering::Relaxed);
+            }
+        }
+        atomic::fence(Ordering::Release);
+        for (bytes, word) in seeds.chunks_exact(core::mem::size_of::<usize>()).zip(self.seeds.iter()) {
+            word.store(usize::from_ne_bytes(bytes.try_into().unwrap()), Ordering::Relaxed);
+        }
+        self.counter.store(&PI as *const _ as usize, Ordering::Relaxed);
+        // Zero is skipped, as it means that the seeds were never written.
+        self.generation.store(generation.wrapping_add(2).max(2), Ordering::Release);
+    }
+}
+
+#[cfg(feature = "runtime-rng")]
+#[allow(clippy::declare_interior_mutable_const)]
+const UNSET_WORD: AtomicUsize = AtomicUsize::new(0);
+
+#[cfg(feature = "runtime-rng")]
+static FORK_SEEDS: ForkSeeds = ForkSeeds {
+    generation: AtomicUsize::new(0),
+    seeds: [UNSET_WORD; FORK_SEED_WORDS],
+    counter: AtomicUsize::new(0),
+};
+
+/// Returns the generation and the seeds written by the most recent call to [reseed_after_fork], if any.
+#[cfg(feature = "runtime-rng")]
+#[inline]
+fn get_fork_seeds() -> Option<(usize, [[u64; 4]; 2])> {
+    #[cfg(feature = "fork-safe")]
+    if let Err(error) = reseed_if_forked() {
+        // With the other policies the seeds inherited from the parent are kept.
+        if let SeedFailurePolicy::Panic = seed_failure_policy() {
+            panic!("getrandom::getrandom() failed. {}", error);
+        }
+    }
+    FORK_SEEDS.load()
+}
+
+/// The seeds used to derive the keys of each [RandomState::new()]. These are the process wide seeds unless
+/// [reseed_after_fork] has been called.
+#[inline]
+fn get_key_seeds() -> [[u64; 4]; 2] {
+    #[cfg(feature = "testing")]
+    if let Some(deterministic) = crate::testing::deterministic() {
+        return *deterministic.fixed_seeds();
+    }
+    #[cfg(feature = "runtime-rng")]
+    if let Some((_, seeds)) = get_fork_seeds() {
+        return seeds;
+    }
+    *get_fixed_seeds()
+}
+
+/// Changes each time [reseed_after_fork] is called, so that values derived from [get_key_seeds] can be cached
+/// without reading the seeds again.
+#[cfg(all(feature = "std", not(all(target_arch = "arm", target_os = "none"))))]
+#[inline]
+fn get_key_seeds_generation() -> usize {
+    cfg_if::cfg_if! {
+        if #[cfg(feature = "runtime-rng")] {
+            get_fork_seeds().map_or(0, |(generation, _)| generation)
+        } else {
+            0
+        }
+    }
+}
+
+/// True if [reseed_after_fork] has been called in this process. (See [config](crate::config()))
+///
+/// Like [seed_source] this only reads the state, so with the `fork-safe` feature it does not reseed if the process
+/// id has changed. That happens when the next [RandomState] is created.
+pub(crate) fn reseeded_after_fork() -> bool {
+    #[cfg(feature = "testing")]
+    if let Some(Some(_)) = crate::testing::deterministic_if_read() {
+        return false;
+    }
+    cfg_if::cfg_if! {
+        if #[cfg(feature = "runtime-rng")] {
+            FORK_SEEDS.generation.load(Ordering::Relaxed) != 0
+        } else {
+            false
+        }
+    }
+}
+
+/// Gives this process new seeds for the keys of each [RandomState::new()], so that they differ from those in the
+/// process it was forked from.
+///
+/// When a process forks, the child inherits a copy of the seeds and of the counter used to derive the keys of each
+/// [RandomState]. So without this the states created by the parent and the child (or by several children of the same
+/// parent, as in a pre-forked pool of workers) will have the same keys. Calling this in the child immediately after
+/// the fork prevents that: every [RandomState::new()] and [RandomState::generate_with] called afterwards derives its
+/// keys from new random seeds and a new counter, so they are unrelated to those in any other process.
+///
+/// The process wide seeds are not changed, so [RandomState::with_seed], [Default] for [AHasher] and the other
+/// hashers with fixed keys return the same keys as in the parent, and maps inherited from it can still be read. If a
+/// custom source was installed with [set_random_source] and it overrides [RandomSource::gen_hasher_keys], that source
+/// is responsible for its own state after a fork.
+///
+/// Calling this again replaces the seeds in place, so it does not allocate.
+///
+/// With the `fork-safe` feature this is done automatically when aHash notices that the process id has changed. If
+/// that fails, it is not tried again until the next fork: until then [RandomState::try_new] returns the error and
+/// [RandomState::new()] panics if the [SeedFailurePolicy] is to panic. (A successful call to this function clears it)
+///
+/// # Errors
+/// If the operating system fails to provide random numbers. The existing seeds are then kept, as seeds provided by
+/// the [SeedFailurePolicy] would be the same in every child. (The policy is not applied)
+#[cfg(feature = "runtime-rng")]
+pub fn reseed_after_fork() -> Result<(), SeedError> {
+    let mut seeds = [0_u8; 64];
+    getrandom::getrandom(&mut seeds).map_err(|error| SeedError { error })?;
+    FORK_SEEDS.store(&seeds);
+    #[cfg(feature = "fork-safe")]
+    RESEED_ERROR.store(0, Ordering::Relaxed);
+    Ok(())
+}
+
+/// The error code of `getrandom` if reseeding automatically after the last fork failed, or zero.
+#[cfg(feature = "fork-safe")]
+static RESEED_ERROR: atomic::AtomicU32 = atomic::AtomicU32::new(0);
+
+/// The process id when the seeds or the counter were set up, or last used. Zero if neither has been set up yet.
+#[cfg(feature = "fork-safe")]
+static PID: atomic::AtomicU32 = atomic::AtomicU32::new(0);
+
+/// Records the process id when the process wide seeds or the counter of the default [RandomSource] are set up. A
+/// process forked after that reseeds, even if this one never called [RandomState::new()].
+#[cfg(feature = "fork-safe")]
+fn record_pid() {
+    let _ = PID.compare_exchange(0, std::process::id(), Ordering::Relaxed, Ordering::Relaxed);
+}
+
+/// Calls [reseed_after_fork] if the process id differs from the one recorded when the seeds or the counter were set
+/// up, or the last time they were used.
+///
+/// This adds a `getpid` call each time the seeds are used. If reseeding fails, the error is returned by this and every
+/// later call until the next fork, rather than trying again each time.
+#[cfg(feature = "fork-safe")]
+#[inline]
+fn reseed_if_forked() -> Result<(), SeedError> {
+    let pid = std::process::id();
+    let previous = PID.load(Ordering::Relaxed);
+    // If nothing was set up before, the seeds were not inherited and the process id only needs to be recorded.
+    if previous != pid
+        && PID.compare_exchange(previous, pid, Ordering::Relaxed, Ordering::Relaxed).is_ok()
+        && previous != 0
+    {
+        let error = reseed_after_fork().err().map_or(0, |error| error.error.code().get());
+        RESEED_ERROR.store(error, Ordering::Relaxed);
+    }
+    match core::num::NonZeroU32::new(RESEED_ERROR.load(Ordering::Relaxed)) {
+        Some(code) => Err(SeedError { error: code.into() }),
+        None => Ok(()),
     }
 }
 
@@ -131,6 +548,26 @@ cfg_if::cfg_if! {
 /// (Rust enables ASLR by default)
 pub trait RandomSource {
     fn gen_hasher_seed(&self) -> usize;
+
+    /// Generates the four keys for a new [RandomState].
+    ///
+    /// The default implementation stretches the result of [RandomSource::gen_hasher_seed] into four keys by mixing it
+    /// with the process wide seeds. Because that is only a `usize`, each [RandomState] then only gets 32 bits of its
+    /// own entropy on 32 bit platforms. A source which can produce more randomness should override this method.
+    ///
+    /// The returned keys are used as is (as with [RandomState::from_seeds]), so they should be of high quality.
+    fn gen_hasher_keys(&self) -> [u64; 4] {
+        let seeds = get_key_seeds();
+        RandomState::from_keys(&seeds[0], &seeds[1], self.gen_hasher_seed()).to_seeds()
+    }
+
+    /// The same as [RandomSource::gen_hasher_keys], but returning an error instead of applying the
+    /// [SeedFailurePolicy] if the keys cannot be generated. This is used by [RandomState::try_new].
+    ///
+    /// The default implementation calls [RandomSource::gen_hasher_keys], and so never returns an error.
+    fn try_gen_hasher_keys(&self) -> Result<[u64; 4], SeedError> {
+        Ok(self.gen_hasher_keys())
+    }
 }
 
 struct DefaultRandomSource {
@@ -139,6 +576,8 @@ struct DefaultRandomSource {
 
 impl DefaultRandomSource {
     fn new() -> DefaultRandomSource {
+        #[cfg(feature = "fork-safe")]
+        record_pid();
         DefaultRandomSource {
             counter: AtomicUsize::new(&PI as *const _ as usize),
         }
@@ -167,8 +606,196 @@ impl RandomSource for DefaultRandomSource {
                 let stack = self as *const _ as usize;
                 self.counter.fetch_add(stack, Ordering::Relaxed)
             }
+
+            /// After [reseed_after_fork] the counter inherited from the parent is replaced by a new one.
+            #[cfg(feature = "runtime-rng")]
+            fn gen_hasher_keys(&self) -> [u64; 4] {
+                match get_fork_seeds() {
+                    Some((_, seeds)) => {
+                        let stack = self as *const _ as usize;
+                        let counter = FORK_SEEDS.counter.fetch_add(stack, Ordering::Relaxed);
+                        RandomState::from_keys(&seeds[0], &seeds[1], counter).to_seeds()
+                    }
+                    None => {
+                        let fixed = get_fixed_seeds();
+                        RandomState::from_keys(&fixed[0], &fixed[1], self.gen_hasher_seed()).to_seeds()
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// A [RandomSource] which takes the keys of each [RandomState] from a random number generator.
+/// (Requires the `rand_core` feature)
+///
+/// Each [RandomState::new()] draws 256 bits from the generator, so if it is a CSPRNG every map has independent keys
+/// which do not depend on the process wide seeds. Because the generator is shared it is protected by a mutex.
+#[cfg_attr(
+    feature = "rand_core",
+    doc = r##" # Examples
+```
+use ahash::random_state::{set_random_source, RngSource};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+set_random_source(RngSource::new(StdRng::from_entropy())).expect("Random source was already set");
+```
+"##
+)]
+#[cfg(feature = "rand_core")]
+pub struct RngSource<R> {
+    rng: std::sync::Mutex<R>,
+}
+
+#[cfg(feature = "rand_core")]
+impl<R: rand_core::RngCore> RngSource<R> {
+    /// Creates a source which draws keys from `rng`.
+    pub fn new(rng: R) -> RngSource<R> {
+        RngSource {
+            rng: std::sync::Mutex::new(rng),
+        }
+    }
+}
+
+#[cfg(feature = "rand_core")]
+impl<R: rand_core::RngCore> RandomSource for RngSource<R> {
+    fn gen_hasher_seed(&self) -> usize {
+        self.gen_hasher_keys()[0] as usize
+    }
+
+    fn gen_hasher_keys(&self) -> [u64; 4] {
+        // A panic while the lock is held cannot leave the generator in a state that is unsafe to use.
+        let mut rng = self.rng.lock().unwrap_or_else(|e| e.into_inner());
+        [rng.next_u64(), rng.next_u64(), rng.next_u64(), rng.next_u64()]
+    }
+}
+
+/// A [RandomSource] which generates the keys of each [RandomState] from a per thread stream. (Requires the `std`
+/// feature)
+///
+/// The default source increments a shared atomic counter for each [RandomState::new()], and then derives the keys
+/// by mixing it with the process wide seeds, which takes four rounds of the hasher. In programs which create many
+/// short lived maps on many threads this can be noticeable, both because of the work and because every thread
+/// writes to the same cache line.
+///
+/// This source instead gives each thread a hasher and a 256 bit state, both derived from the process wide seeds and
+/// a number unique to the thread when the thread first uses the source. For each [RandomState] the state is hashed
+/// four times with different tags: two of the 128 bit outputs are the keys and the other two replace the state. No
+/// shared memory is written after the first use on each thread. The keys are as unpredictable as with the default
+/// source, as they depend on the same secret seeds.
+///
+/// After [reseed_after_fork](crate::reseed_after_fork) each thread's stream is rekeyed the next time it is used.
+#[cfg_attr(
+    feature = "std",
+    doc = r##" # Examples
+```
+use ahash::random_state::{set_random_source, ThreadLocalRandomSource};
+
+set_random_source(ThreadLocalRandomSource).expect("Random source was already set");
+```
+"##
+)]
+#[cfg(all(feature = "std", not(all(target_arch = "arm", target_os = "none"))))]
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ThreadLocalRandomSource;
+
+#[cfg(all(feature = "std", not(all(target_arch = "arm", target_os = "none"))))]
+struct SeedStream {
+    generation: usize,
+    hasher: AHasher,
+    state: [u64; 4],
+}
+
+#[cfg(all(feature = "std", not(all(target_arch = "arm", target_os = "none"))))]
+impl SeedStream {
+    fn new(generation: usize) -> SeedStream {
+        static THREADS: AtomicUsize = AtomicUsize::new(0);
+
+        let seeds = get_key_seeds();
+        let thread = THREADS.fetch_add(1, Ordering::Relaxed);
+        SeedStream {
+            generation,
+            hasher: AHasher::from_random_state(&RandomState::from_keys(&seeds[0], &seeds[1], thread)),
+            state: RandomState::from_keys(&seeds[1], &seeds[0], thread).to_seeds(),
         }
     }
+
+    #[inline]
+    fn next_keys(&mut self) -> [u64; 4] {
+        let mut hasher = self.hasher.clone();
+        for word in self.state {
+            hasher.write_u64(word);
+        }
+        let block = |tag: u8| {
+            let mut hasher = hasher.clone();
+            hasher.write_u8(tag);
+            hasher.finish_u128()
+        };
+        let (a, b, c, d) = (block(0), block(1), block(2), block(3));
+        self.state = [c as u64, (c >> 64) as u64, d as u64, (d >> 64) as u64];
+        [a as u64, (a >> 64) as u64, b as u64, (b >> 64) as u64]
+    }
+}
+
+#[cfg(all(feature = "std", not(all(target_arch = "arm", target_os = "none"))))]
+impl RandomSource for ThreadLocalRandomSource {
+    fn gen_hasher_seed(&self) -> usize {
+        self.gen_hasher_keys()[0] as usize
+    }
+
+    #[inline]
+    fn gen_hasher_keys(&self) -> [u64; 4] {
+        std::thread_local! {
+            static STREAM: core::cell::RefCell<Option<SeedStream>> = const { core::cell::RefCell::new(None) };
+        }
+
+        let generation = get_key_seeds_generation();
+        STREAM.with(|stream| {
+            let mut stream = stream.borrow_mut();
+            if !matches!(&*stream, Some(s) if s.generation == generation) {
+                *stream = Some(SeedStream::new(generation));
+            }
+            stream.as_mut().unwrap().next_keys()
+        })
+    }
+}
+
+/// A [RandomSource] which obtains the keys of each [RandomState] from the operating system using
+/// [getrandom](https://docs.rs/getrandom). (Requires the `runtime-rng` feature)
+///
+/// By default only the process wide seeds come from the operating system, and the keys of each [RandomState] are
+/// derived from them and a counter. This instead makes a system call for each [RandomState::new()], which is much
+/// slower but gives every map 256 bits of independent randomness.
+///
+/// If the operating system fails to provide random numbers [RandomState::try_new] returns the error, and
+/// [RandomState::new()] applies the [SeedFailurePolicy]. (Mixing the seeds it provides with a counter)
+#[cfg(feature = "runtime-rng")]
+#[derive(Debug, Default, Clone, Copy)]
+pub struct GetrandomSource;
+
+#[cfg(feature = "runtime-rng")]
+impl RandomSource for GetrandomSource {
+    fn gen_hasher_seed(&self) -> usize {
+        self.gen_hasher_keys()[0] as usize
+    }
+
+    fn gen_hasher_keys(&self) -> [u64; 4] {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+        self.try_gen_hasher_keys().unwrap_or_else(|error| {
+            let seeds = apply_policy(seed_failure_policy(), error);
+            RandomState::from_keys(&seeds[0], &seeds[1], COUNTER.fetch_add(1, Ordering::Relaxed)).to_seeds()
+        })
+    }
+
+    fn try_gen_hasher_keys(&self) -> Result<[u64; 4], SeedError> {
+        use crate::convert::Convert;
+
+        let mut result: [u8; 32] = [0; 32];
+        getrandom::getrandom(&mut result).map_err(|error| SeedError { error })?;
+        Ok(result.convert())
+    }
 }
 
 cfg_if::cfg_if! {
@@ -178,6 +805,10 @@ cfg_if::cfg_if! {
                 static RAND_SOURCE: DefaultRandomSource = DefaultRandomSource::default();
                 &RAND_SOURCE
             }
+
+            pub(crate) fn has_custom_source() -> bool {
+                false
+            }
         } else {
             /// Provides an optional way to manually supply a source of randomness for Hasher keys.
             ///
@@ -189,11 +820,25 @@ cfg_if::cfg_if! {
             /// method was previously invoked (true) or if the default source is already being used (false).
             #[cfg(not(all(target_arch = "arm", target_os = "none")))]
             pub fn set_random_source(source: impl RandomSource + Send + Sync + 'static) -> Result<(), bool> {
-                RAND_SOURCE.set(Box::new(Box::new(source))).map_err(|s| s.as_ref().type_id() != TypeId::of::<&DefaultRandomSource>())
+                RAND_SOURCE
+                    .set(Box::new(Box::new(source)))
+                    .map_err(|_| has_custom_source())
+                    .map(|()| CUSTOM_SOURCE.store(true, Ordering::Relaxed))
+            }
+
+            static CUSTOM_SOURCE: atomic::AtomicBool = atomic::AtomicBool::new(false);
+
+            /// True if a source was installed with [set_random_source].
+            pub(crate) fn has_custom_source() -> bool {
+                CUSTOM_SOURCE.load(Ordering::Relaxed)
             }
 
             #[inline]
             fn get_src() -> &'static dyn RandomSource {
+                #[cfg(feature = "testing")]
+                if let Some(deterministic) = crate::testing::deterministic() {
+                    return deterministic;
+                }
                 RAND_SOURCE.get_or_init(|| Box::new(Box::new(DefaultRandomSource::new()))).as_ref()
             }
         }
@@ -238,9 +883,30 @@ impl RandomState {
     ///
     #[inline]
     pub fn new() -> RandomState {
-        let src = get_src();
-        let fixed = get_fixed_seeds();
-        Self::from_keys(&fixed[0], &fixed[1], src.gen_hasher_seed())
+        Self::from_source(get_src())
+    }
+
+    /// Create a new `RandomState` `BuildHasher` using random keys, or return an error if the operating system fails to
+    /// provide the random numbers used to seed it.
+    ///
+    /// [RandomState::new()] and [Default] cannot return an error, so if this happens they apply the
+    /// [SeedFailurePolicy] instead, which by default is to panic. This allows the failure to be handled where it
+    /// occurs. This covers obtaining the process wide seeds, reseeding after a fork with the `fork-safe` feature,
+    /// and sources such as `GetrandomSource` which obtain the keys of each state from the operating system. (A custom
+    /// source installed with [set_random_source] reports its errors through [RandomSource::try_gen_hasher_keys])
+    #[inline]
+    pub fn try_new() -> Result<RandomState, SeedError> {
+        try_get_fixed_seeds()?;
+        #[cfg(feature = "fork-safe")]
+        reseed_if_forked()?;
+        let [k0, k1, k2, k3] = get_src().try_gen_hasher_keys()?;
+        Ok(RandomState { k0, k1, k2, k3 })
+    }
+
+    #[inline]
+    fn from_source(src: &dyn RandomSource) -> RandomState {
+        let [k0, k1, k2, k3] = src.gen_hasher_keys();
+        RandomState { k0, k1, k2, k3 }
     }
 
     /// Create a new `RandomState` `BuildHasher` based on the provided seeds, but in such a way
@@ -255,11 +921,11 @@ impl RandomState {
     #[inline]
     pub fn generate_with(k0: u64, k1: u64, k2: u64, k3: u64) -> RandomState {
         let src = get_src();
-        let fixed = get_fixed_seeds();
-        RandomState::from_keys(&fixed[0], &[k0, k1, k2, k3], src.gen_hasher_seed())
+        let seeds = get_key_seeds();
+        RandomState::from_keys(&seeds[0], &[k0, k1, k2, k3], src.gen_hasher_seed())
     }
 
-    fn from_keys(a: &[u64; 4], b: &[u64; 4], c: usize) -> RandomState {
+    pub(crate) fn from_keys(a: &[u64; 4], b: &[u64; 4], c: usize) -> RandomState {
         let &[k0, k1, k2, k3] = a;
         let mut hasher = AHasher::from_random_state(&RandomState { k0, k1, k2, k3 });
         hasher.write_usize(c);
@@ -315,6 +981,40 @@ impl RandomState {
         }
     }
 
+    /// Returns the keys used by this `RandomState`.
+    ///
+    /// These can be passed to [RandomState::from_seeds] to recreate an identical `RandomState`, for example in another
+    /// process which needs to produce the same hashes. (Note that they are not the values that were passed to
+    /// [RandomState::with_seeds], and the hashes themselves may still differ between versions of aHash or CPUs)
+    ///
+    /// The keys are what makes the hashes unpredictable. If DOS resistance is desired, they must be kept secret.
+    #[inline]
+    pub const fn to_seeds(&self) -> [u64; 4] {
+        [self.k0, self.k1, self.k2, self.k3]
+    }
+
+    /// Recreates a `RandomState` from the keys returned by [RandomState::to_seeds].
+    ///
+    /// Unlike [RandomState::with_seeds] the values are used exactly as they are, so they should come from `to_seeds`
+    /// rather than being chosen by hand.
+    #[cfg_attr(
+    feature = "std",
+    doc = r##" # Examples
+```
+    use ahash::RandomState;
+
+    let hash_builder = RandomState::with_seed(42);
+    let copy = RandomState::from_seeds(hash_builder.to_seeds());
+    assert_eq!(hash_builder.hash_one("Some Data"), copy.hash_one("Some Data"));
+```
+    "##
+    )]
+    #[inline]
+    pub const fn from_seeds(seeds: [u64; 4]) -> RandomState {
+        let [k0, k1, k2, k3] = seeds;
+        RandomState { k0, k1, k2, k3 }
+    }
+
     /// Calculates the hash of a single value. This provides a more convenient (and faster) way to obtain a hash:
     /// For example:
     #[cfg_attr(
@@ -362,6 +1062,162 @@ impl RandomState {
         use crate::specialize::CallHasher;
         T::get_hash(&x, self)
     }
+
+    /// Calculates the hashes of many values at once, storing the hash of `values[i]` in `hashes[i]`.
+    ///
+    /// Each result is identical to calling [RandomState::hash_one] on the corresponding value. The values are hashed
+    /// four at a time, so the independent chains of multiplications or AES rounds can be overlapped by the CPU.
+    /// (When aHash is built with specialization, `u32`, `u64`, `u128`, `String` and `Vec<u8>` are hashed by kernels
+    /// written for the type, which keep the four states in registers and are considerably faster)
+    ///
+    /// # Panics
+    /// If `values` and `hashes` have different lengths.
+    #[cfg_attr(
+    feature = "std",
+    doc = r##" # Examples
+```
+    use ahash::RandomState;
+
+    let hash_builder = RandomState::with_seed(42);
+    let keys: Vec<u64> = (0..100).collect();
+    let mut hashes = vec![0; keys.len()];
+    hash_builder.hash_many(&keys, &mut hashes);
+    assert_eq!(hashes[7], hash_builder.hash_one(7_u64));
+```
+    "##
+    )]
+    #[inline]
+    pub fn hash_many<T: Hash>(&self, values: &[T], hashes: &mut [u64]) {
+        use crate::specialize::CallHasherMany;
+        T::get_hashes(values, self, hashes)
+    }
+
+    /// Calculates a 128 bit hash of a single value.
+    ///
+    /// This is intended for cases where a 64 bit hash would result in too many collisions, such as content
+    /// fingerprints or double hashing in probabilistic data structures. It is equivalent to:
+    #[cfg_attr(
+    feature = "std",
+    doc = r##" # Examples
+```
+    use std::hash::{BuildHasher, Hash};
+    use ahash::RandomState;
+
+    let hash_builder = RandomState::with_seed(42);
+    let mut hasher = hash_builder.build_hasher();
+    "Some Data".hash(&mut hasher);
+    assert_eq!(hasher.finish_u128(), hash_builder.hash_one_u128("Some Data"));
+```
+    "##
+    )]
+    /// (Note that the lower 64 bits of the result may not match the value returned by [RandomState::hash_one] for
+    /// the same data)
+    #[inline]
+    pub fn hash_one_u128<T: Hash>(&self, x: T) -> u128 {
+        let mut hasher = self.build_hasher();
+        x.hash(&mut hasher);
+        hasher.finish_u128()
+    }
+
+    /// Hashes a `u64` using a shorter path than a general purpose [AHasher], which makes it considerably faster.
+    ///
+    /// This does not require specialization, so the speedup is available on stable Rust. The result is the same as
+    /// [RandomState::hash_one] returns for a `u64` when aHash is built with specialization, but may differ from it
+    /// otherwise. To use this in a map, see [KeyRandomState](crate::KeyRandomState).
+    #[inline]
+    pub fn hash_u64(&self, value: u64) -> u64 {
+        self.hash_as_u64(&value)
+    }
+
+    /// Hashes a string using a shorter path than a general purpose [AHasher], which is faster for short strings.
+    ///
+    /// This is identical to [RandomState::hash_bytes] of the string's bytes. (See [RandomState::hash_u64] regarding
+    /// specialization)
+    #[inline]
+    pub fn hash_str(&self, value: &str) -> u64 {
+        self.hash_as_str(value)
+    }
+
+    /// Hashes a slice of bytes using a shorter path than a general purpose [AHasher], which is faster for short
+    /// inputs. (See [RandomState::hash_u64] regarding specialization)
+    #[inline]
+    pub fn hash_bytes(&self, value: &[u8]) -> u64 {
+        self.hash_as_str(value)
+    }
+
+    /// Hashes everything that can be read from `reader` with an [AHashStream](crate::AHashStream).
+    ///
+    /// The result only depends on the bytes read, not on how the reader splits them up, so it is the same as writing
+    /// all of the data to a single `AHashStream` created from this state. (Requires the `std` feature to be enabled.)
+    #[cfg_attr(
+    feature = "std",
+    doc = r##" # Examples
+```
+    use ahash::{AHashStream, RandomState};
+    use std::hash::Hasher;
+
+    let hash_builder = RandomState::with_seed(42);
+    let mut stream = AHashStream::new(&hash_builder);
+    stream.write(b"Some Data");
+    assert_eq!(stream.finish(), hash_builder.hash_reader(&b"Some Data"[..]).unwrap());
+```
+    "##
+    )]
+    #[cfg(feature = "std")]
+    pub fn hash_reader<R: std::io::Read>(&self, mut reader: R) -> std::io::Result<u64> {
+        let mut stream = crate::AHashStream::new(self);
+        std::io::copy(&mut reader, &mut stream)?;
+        Ok(stream.finish())
+    }
+
+    /// Hashes the output of a value's [Display](fmt::Display) implementation without allocating a `String`.
+    ///
+    /// The text is written to an [AHashStream](crate::AHashStream) created from this state, so the result is the same
+    /// however the implementation splits up its output, and equal to writing the fully formatted string to a single
+    /// stream. (It is not the same as [RandomState::hash_str] of that string) To hash the [Debug](fmt::Debug) output
+    /// instead, use `write!(stream, "{:?}", value)` with an `AHashStream`.
+    ///
+    /// # Example
+    /// ```
+    /// use ahash::RandomState;
+    ///
+    /// let hash_builder = RandomState::with_seed(42);
+    /// let query = format!("SELECT {} FROM {}", "*", "table");
+    /// assert_eq!(hash_builder.hash_display(&query), hash_builder.hash_display(&"SELECT * FROM table"));
+    /// ```
+    pub fn hash_display<T: fmt::Display + ?Sized>(&self, value: &T) -> u64 {
+        let mut stream = crate::AHashStream::new(self);
+        // Writing to a stream never fails, so an error can only come from a faulty `Display` implementation, in
+        // which case the output written so far is hashed.
+        let _ = fmt::Write::write_fmt(&mut stream, format_args!("{}", value));
+        stream.finish()
+    }
+
+    /// Hashes a large buffer using all of the threads in the current [rayon] thread pool.
+    ///
+    /// The data is split into fixed size leaves which are hashed independently and then combined in a binary tree.
+    /// The shape of the tree only depends on the length of the data, so the result is the same regardless of the
+    /// number of threads. It is not the same as the result of writing the data to an [AHasher].
+    ///
+    /// This is only faster than a single thread for inputs of at least several megabytes, such as memory mapped
+    /// files. (Requires the `rayon` feature to be enabled.)
+    #[cfg_attr(
+    feature = "rayon",
+    doc = r##" # Examples
+```
+    use ahash::RandomState;
+
+    let hash_builder = RandomState::with_seed(42);
+    let data = vec![7_u8; 4 << 20];
+    let single = rayon::ThreadPoolBuilder::new().num_threads(1).build().unwrap();
+    assert_eq!(single.install(|| hash_builder.hash_large_parallel(&data)), hash_builder.hash_large_parallel(&data));
+```
+    "##
+    )]
+    #[cfg(feature = "rayon")]
+    pub fn hash_large_parallel(&self, data: &[u8]) -> u64 {
+        crate::parallel::hash_large_parallel(self, data)
+    }
 }
 
 /// Creates an instance of RandomState using keys obtained from the random number generator.
@@ -381,6 +1237,22 @@ impl Default for RandomState {
     }
 }
 
+/// Serializes the keys as returned by [RandomState::to_seeds].
+/// (Anyone who can read the serialized form can predict the hashes. See [RandomState::to_seeds])
+#[cfg(feature = "serde")]
+impl serde::Serialize for RandomState {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.to_seeds().serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for RandomState {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        <[u64; 4]>::deserialize(deserializer).map(RandomState::from_seeds)
+    }
+}
+
 impl BuildHasher for RandomState {
     type Hasher = AHasher;
 
@@ -467,10 +1339,12 @@ impl BuildHasher for RandomState {
     }
 }
 
-#[cfg(feature = "specialize")]
-impl BuildHasherExt for RandomState {
+/// The optimized paths for hashing a single value of a particular kind of type.
+/// These are used by specialization when it is available, and by [AHashKey](crate::AHashKey) otherwise.
+impl RandomState {
+    /// Hashes a value which only writes a single primitive of 64 bits or less.
     #[inline]
-    fn hash_as_u64<T: Hash + ?Sized>(&self, value: &T) -> u64 {
+    pub(crate) fn hash_as_u64<T: Hash + ?Sized>(&self, value: &T) -> u64 {
         let mut hasher = AHasherU64 {
             buffer: self.k0,
             pad: self.k1,
@@ -479,18 +1353,116 @@ impl BuildHasherExt for RandomState {
         hasher.finish()
     }
 
+    /// Hashes many values which only write a single primitive of 64 bits or less.
+    #[cfg(feature = "specialize")]
+    #[inline]
+    pub(crate) fn hash_many_as_u64<V>(&self, values: &[V], hashes: &mut [u64])
+    where
+        AHasherU64: HashX4<V>,
+    {
+        let hasher = AHasherU64 {
+            buffer: self.k0,
+            pad: self.k1,
+        };
+        hash_by_4(&hasher, values, hashes);
+    }
+
+    cfg_if::cfg_if! {
+        if #[cfg(all(
+            feature = "runtime-dispatch",
+            not(feature = "force-fallback"),
+            any(target_arch = "x86", target_arch = "x86_64"),
+            not(target_feature = "aes"),
+            not(miri)
+        ))] {
+            /// Hashes a value which only writes a single fixed size primitive larger than 64 bits.
+            #[inline]
+            pub(crate) fn hash_as_fixed_length<T: Hash + ?Sized>(&self, value: &T) -> u64 {
+                crate::dispatch_hash::hash_as_fixed_length(self, value)
+            }
+
+            /// Hashes a value which writes a single string or slice of bytes.
+            #[inline]
+            pub(crate) fn hash_as_str<T: Hash + ?Sized>(&self, value: &T) -> u64 {
+                crate::dispatch_hash::hash_as_str(self, value)
+            }
+
+            /// Hashes many fixed size primitives larger than 64 bits.
+            #[cfg(feature = "specialize")]
+            #[inline]
+            pub(crate) fn hash_many_as_fixed_length(&self, values: &[u128], hashes: &mut [u64]) {
+                crate::dispatch_hash::hash_many_u128(self, values, hashes)
+            }
+
+            /// Hashes many strings or slices of bytes.
+            #[cfg(feature = "specialize")]
+            #[inline]
+            pub(crate) fn hash_many_as_str<B: AsRef<[u8]>>(&self, values: &[B], hashes: &mut [u64]) {
+                crate::dispatch_hash::hash_many_bytes(self, values, hashes)
+            }
+        } else {
+            /// Hashes a value which only writes a single fixed size primitive larger than 64 bits.
+            #[inline]
+            pub(crate) fn hash_as_fixed_length<T: Hash + ?Sized>(&self, value: &T) -> u64 {
+                let mut hasher = AHasherFixed(self.build_hasher());
+                value.hash(&mut hasher);
+                hasher.finish()
+            }
+
+            /// Hashes a value which writes a single string or slice of bytes.
+            #[inline]
+            pub(crate) fn hash_as_str<T: Hash + ?Sized>(&self, value: &T) -> u64 {
+                let mut hasher = AHasherStr(self.build_hasher());
+                value.hash(&mut hasher);
+                hasher.finish()
+            }
+
+            /// Hashes many fixed size primitives larger than 64 bits.
+            #[cfg(feature = "specialize")]
+            #[inline]
+            pub(crate) fn hash_many_as_fixed_length(&self, values: &[u128], hashes: &mut [u64]) {
+                hash_by_4(&AHasherFixed(self.build_hasher()), values, hashes);
+            }
+
+            /// Hashes many strings or slices of bytes.
+            #[cfg(feature = "specialize")]
+            #[inline]
+            pub(crate) fn hash_many_as_str<B: AsRef<[u8]>>(&self, values: &[B], hashes: &mut [u64]) {
+                hash_by_4(&AHasherStr(self.build_hasher()), values, hashes);
+            }
+        }
+    }
+}
+
+/// Used by [RandomState::hash_many] for types without a kernel of their own.
+impl<T: Hash> HashX4<T> for RandomState {
+    #[inline(always)]
+    fn hash_x4(&self, values: [&T; 4]) -> [u64; 4] {
+        use crate::specialize::CallHasher;
+        [
+            T::get_hash(values[0], self),
+            T::get_hash(values[1], self),
+            T::get_hash(values[2], self),
+            T::get_hash(values[3], self),
+        ]
+    }
+}
+
+#[cfg(feature = "specialize")]
+impl BuildHasherExt for RandomState {
+    #[inline]
+    fn hash_as_u64<T: Hash + ?Sized>(&self, value: &T) -> u64 {
+        RandomState::hash_as_u64(self, value)
+    }
+
     #[inline]
     fn hash_as_fixed_length<T: Hash + ?Sized>(&self, value: &T) -> u64 {
-        let mut hasher = AHasherFixed(self.build_hasher());
-        value.hash(&mut hasher);
-        hasher.finish()
+        RandomState::hash_as_fixed_length(self, value)
     }
 
     #[inline]
     fn hash_as_str<T: Hash + ?Sized>(&self, value: &T) -> u64 {
-        let mut hasher = AHasherStr(self.build_hasher());
-        value.hash(&mut hasher);
-        hasher.finish()
+        RandomState::hash_as_str(self, value)
     }
 }
 
@@ -523,6 +1495,162 @@ mod test {
         assert_eq!(PI, get_fixed_seeds()[0]);
     }
 
+    #[test]
+    fn test_default_source_keys() {
+        struct Counter;
+        impl RandomSource for Counter {
+            fn gen_hasher_seed(&self) -> usize {
+                7
+            }
+        }
+        let fixed = get_fixed_seeds();
+        let expected = RandomState::from_keys(&fixed[0], &fixed[1], 7);
+        assert_eq!(expected.to_seeds(), RandomState::from_source(&Counter).to_seeds());
+    }
+
+    #[cfg(feature = "rand_core")]
+    #[test]
+    fn test_rng_source() {
+        let source = RngSource::new(rand::rngs::mock::StepRng::new(10, 1));
+        assert_eq!([10, 11, 12, 13], RandomState::from_source(&source).to_seeds());
+        assert_eq!([14, 15, 16, 17], RandomState::from_source(&source).to_seeds());
+        assert_eq!(18, source.gen_hasher_seed());
+    }
+
+    #[cfg(feature = "runtime-rng")]
+    #[test]
+    fn test_getrandom_source() {
+        let a = RandomState::from_source(&GetrandomSource);
+        let b = RandomState::from_source(&GetrandomSource);
+        assert_ne!(a.to_seeds(), b.to_seeds());
+        assert_ne!(a.hash_one("test"), b.hash_one("test"));
+        assert_ne!(GetrandomSource.try_gen_hasher_keys().unwrap(), GetrandomSource.try_gen_hasher_keys().unwrap());
+    }
+
+    #[test]
+    fn test_try_new() {
+        let a = RandomState::try_new().unwrap();
+        let b = RandomState::try_new().unwrap();
+        assert_ne!(a.to_seeds(), b.to_seeds());
+        assert_eq!(get_fixed_seeds(), try_get_fixed_seeds().unwrap());
+    }
+
+    #[cfg(feature = "runtime-rng")]
+    #[test]
+    fn test_seed_failure_policy() {
+        let error = || SeedError {
+            error: getrandom::Error::from(core::num::NonZeroU32::new(getrandom::Error::CUSTOM_START).unwrap()),
+        };
+        assert!(error().to_string().starts_with("failed to obtain random seeds from the operating system: "));
+        #[cfg(not(feature = "compile-time-rng"))]
+        assert_eq!([PI, PI2], apply_policy(SeedFailurePolicy::FallbackSeeds, error()));
+        let callback = SeedFailurePolicy::Callback(|| [[1; 4], [2; 4]]);
+        assert_eq!([[1; 4], [2; 4]], apply_policy(callback, error()));
+        assert!(std::panic::catch_unwind(|| apply_policy(SeedFailurePolicy::Panic, error())).is_err());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_thread_local_source() {
+        let a = RandomState::from_source(&ThreadLocalRandomSource);
+        let b = RandomState::from_source(&ThreadLocalRandomSource);
+        assert_ne!(a.to_seeds(), b.to_seeds());
+        assert_ne!(a.hash_one("test"), b.hash_one("test"));
+        let other_thread = std::thread::spawn(|| RandomState::from_source(&ThreadLocalRandomSource).to_seeds());
+        let other_thread = other_thread.join().unwrap();
+        assert_ne!(a.to_seeds(), other_thread);
+        assert_ne!(b.to_seeds(), other_thread);
+
+        let mut stream = SeedStream::new(0);
+        let state = stream.state;
+        let keys = stream.next_keys();
+        assert_ne!(keys, state);
+        assert_ne!(keys, stream.state);
+        assert_ne!(state, stream.state);
+        let keys: std::collections::HashSet<_> = (0..1000).map(|_| stream.next_keys()).collect();
+        assert_eq!(1000, keys.len());
+    }
+
+    #[test]
+    fn test_seeds_round_trip() {
+        let a = RandomState::generate_with(1, 2, 3, 4);
+        let b = RandomState::from_seeds(a.to_seeds());
+        assert_eq!(a.to_seeds(), b.to_seeds());
+        assert_eq!(a.hash_one("test"), b.hash_one("test"));
+        assert_eq!(a.hash_one(1234_u64), b.hash_one(1234_u64));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde() {
+        let a = RandomState::generate_with(1, 2, 3, 4);
+        let serialized = serde_json::to_string(&a).unwrap();
+        assert_eq!(serialized, serde_json::to_string(&a.to_seeds()).unwrap());
+        let b: RandomState = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(a.to_seeds(), b.to_seeds());
+        assert_eq!(a.hash_one("test"), b.hash_one("test"));
+    }
+
+    #[test]
+    fn test_hash_one_u128() {
+        let build_hasher = RandomState::with_seeds(1, 2, 3, 4);
+        let mut hasher = build_hasher.build_hasher();
+        "test".hash(&mut hasher);
+        assert_eq!(hasher.finish_u128(), build_hasher.hash_one_u128("test"));
+        assert_eq!(hasher.finish(), build_hasher.hash_one_u128("test") as u64);
+        assert_ne!(build_hasher.hash_one_u128("test"), build_hasher.hash_one_u128("tess"));
+    }
+
+    fn assert_hash_many_matches<T: Hash + Clone>(build_hasher: &RandomState, values: &[T]) {
+        for len in 0..=values.len() {
+            let mut hashes = vec![0; len];
+            build_hasher.hash_many(&values[..len], &mut hashes);
+            for (value, hash) in values[..len].iter().zip(hashes.iter()) {
+                assert_eq!(build_hasher.hash_one(value.clone()), *hash);
+            }
+        }
+    }
+
+    #[test]
+    fn test_hash_many_matches_hash_one() {
+        let build_hasher = RandomState::with_seeds(1, 2, 3, 4);
+        let u32s: Vec<u32> = (0..11).map(|i| i * 0x1234_5679).collect();
+        let u64s: Vec<u64> = (0..11).map(|i| i * 0x1234_5678_9abc_def1).collect();
+        let u128s: Vec<u128> = (0..11).map(|i| (i as u128) << 100 | i as u128).collect();
+        let strings: Vec<String> = (0..11).map(|i| "x".repeat(i * 3)).collect();
+        let strs: Vec<&str> = strings.iter().map(|s| s.as_str()).collect();
+        let bytes: Vec<&[u8]> = strings.iter().map(|s| s.as_bytes()).collect();
+        assert_hash_many_matches(&build_hasher, &u32s);
+        assert_hash_many_matches(&build_hasher, &u64s);
+        assert_hash_many_matches(&build_hasher, &u128s);
+        assert_hash_many_matches(&build_hasher, &strings);
+        assert_hash_many_matches(&build_hasher, &strs);
+        assert_hash_many_matches(&build_hasher, &bytes);
+        assert_hash_many_matches(&build_hasher, &[(1, 2), (3, 4), (5, 6), (7, 8), (9, 10)]);
+    }
+
+    #[test]
+    fn test_hash_many_kernels_match_hash_one() {
+        let build_hasher = RandomState::with_seeds(1, 2, 3, 4);
+        // Short and long values are mixed so that some groups take the lockstep path and others do not.
+        let strings: Vec<String> = (0..11).map(|i| "x".repeat(if i < 6 { i } else { i * 3 })).collect();
+        let vecs: Vec<Vec<u8>> = strings.iter().map(|s| s.clone().into_bytes()).collect();
+        assert_hash_many_matches(&build_hasher, &strings);
+        assert_hash_many_matches(&build_hasher, &vecs);
+        let mut shuffled = strings.clone();
+        shuffled.reverse();
+        assert_hash_many_matches(&build_hasher, &shuffled);
+        assert_hash_many_matches(&build_hasher, &[u32::MAX, 0, 1, u32::MAX - 1, 7]);
+        assert_hash_many_matches(&build_hasher, &[u64::MAX, 0, 1, u64::MAX - 1, 7]);
+        assert_hash_many_matches(&build_hasher, &[u128::MAX, 0, 1, u128::MAX - 1, 7]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_hash_many_length_mismatch() {
+        RandomState::with_seeds(1, 2, 3, 4).hash_many(&[1_u64, 2, 3], &mut [0; 2]);
+    }
+
     #[test]
     fn test_with_seeds_const() {
         const _CONST_RANDOM_STATE: RandomState = RandomState::with_seeds(17, 19, 21, 23);
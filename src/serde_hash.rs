@@ -0,0 +1,721 @@
+//! Hashing of any [Serialize] value, for types whose [Hash](core::hash::Hash) implementation is missing or does not
+//! agree with their `Eq`. (Requires the `serde` feature)
+//!
+//! The [Serializer] writes a canonical encoding of the value to an [AHasher]:
+//!
+//! * Every value starts with a tag for its kind in the serde data model, so for example `1_u32`, `1_u64`, `"1"` and
+//!   `Some(1_u32)` all hash differently.
+//! * The encoding is prefix-free: strings, byte arrays and names are written with their length, each field of a struct
+//!   starts with a tag, and sequences, maps and structs end with a terminator, so `("ab", "c")` and `("a", "bc")` hash
+//!   differently.
+//! * Floats are hashed by value, so `0.0` and `-0.0` hash the same, as do all NaNs.
+//! * Struct fields and enum variants are identified by name, not by their position, but the names of the types
+//!   themselves are not included.
+//!
+//! Maps are hashed in the order in which they are serialized. Types such as `HashMap` serialize their entries in an
+//! order that differs between instances, so for these [Serializer::sort_maps] should be enabled.
+//!
+//! Like the rest of aHash the result depends on the keys of the hasher and is not stable across versions or
+//! platforms.
+//!
+//! # Example
+//! ```
+//! use ahash::{serde_hash, RandomState};
+//! use std::collections::HashMap;
+//!
+//! let state = RandomState::with_seed(42);
+//! let mut a = HashMap::new();
+//! let mut b = HashMap::with_capacity(100);
+//! for i in 0..10 {
+//!     a.insert(i, i.to_string());
+//!     b.insert(9 - i, (9 - i).to_string());
+//! }
+//! assert_eq!(
+//!     serde_hash::hash_with_sorted_maps(&state, &a).unwrap(),
+//!     serde_hash::hash_with_sorted_maps(&state, &b).unwrap()
+//! );
+//! ```
+use crate::{AHasher, RandomState};
+use core::fmt;
+use core::hash::{BuildHasher, Hasher};
+use serde::ser::{self, Serialize};
+
+#[cfg(not(feature = "std"))]
+extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std as alloc;
+
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+/// The tag written before each kind of value.
+mod tag {
+    pub const BOOL: u8 = 1;
+    pub const I8: u8 = 2;
+    pub const I16: u8 = 3;
+    pub const I32: u8 = 4;
+    pub const I64: u8 = 5;
+    pub const I128: u8 = 6;
+    pub const U8: u8 = 7;
+    pub const U16: u8 = 8;
+    pub const U32: u8 = 9;
+    pub const U64: u8 = 10;
+    pub const U128: u8 = 11;
+    pub const F32: u8 = 12;
+    pub const F64: u8 = 13;
+    pub const CHAR: u8 = 14;
+    pub const STR: u8 = 15;
+    pub const BYTES: u8 = 16;
+    pub const NONE: u8 = 17;
+    pub const SOME: u8 = 18;
+    pub const UNIT: u8 = 19;
+    pub const UNIT_STRUCT: u8 = 20;
+    pub const UNIT_VARIANT: u8 = 21;
+    pub const NEWTYPE_STRUCT: u8 = 22;
+    pub const NEWTYPE_VARIANT: u8 = 23;
+    pub const SEQ: u8 = 24;
+    pub const TUPLE: u8 = 25;
+    pub const TUPLE_STRUCT: u8 = 26;
+    pub const TUPLE_VARIANT: u8 = 27;
+    pub const MAP: u8 = 28;
+    pub const SORTED_MAP: u8 = 29;
+    pub const STRUCT: u8 = 30;
+    pub const STRUCT_VARIANT: u8 = 31;
+    /// Ends a sequence, map or struct.
+    pub const END: u8 = 32;
+    /// Starts a field of a struct. (Otherwise the length of its name could be confused with [END])
+    pub const FIELD: u8 = 33;
+}
+
+/// Hashes `value` with a hasher from `state`, writing maps in the order they are serialized.
+#[inline]
+pub fn hash<T: Serialize + ?Sized>(state: &RandomState, value: &T) -> Result<u64, Error> {
+    let mut hasher = state.build_hasher();
+    value.serialize(&mut Serializer::new(&mut hasher))?;
+    Ok(hasher.finish())
+}
+
+/// Hashes `value` with a hasher from `state`, so that the order in which the entries of maps are serialized does not
+/// affect the result. (See [Serializer::sort_maps])
+#[inline]
+pub fn hash_with_sorted_maps<T: Serialize + ?Sized>(state: &RandomState, value: &T) -> Result<u64, Error> {
+    let mut hasher = state.build_hasher();
+    value.serialize(&mut Serializer::new(&mut hasher).sort_maps(true))?;
+    Ok(hasher.finish())
+}
+
+/// An error returned by a [Serialize] implementation.
+///
+/// Hashing itself never fails, but types may report an error while serializing themselves. (For example a `Mutex`
+/// which is poisoned)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Error {
+    message: String,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl ser::StdError for Error {}
+
+impl ser::Error for Error {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        Error {
+            message: msg.to_string(),
+        }
+    }
+}
+
+/// A [serde::Serializer] which writes a canonical encoding of a value to an [AHasher]. (See the [module](self)
+/// documentation)
+///
+/// Values are serialized with `&mut Serializer`, and are written to the hasher as they are serialized, so several
+/// values can be written to the same hasher.
+///
+/// # Example
+/// ```
+/// use ahash::{serde_hash::Serializer, RandomState};
+/// use serde::Serialize;
+/// use std::hash::{BuildHasher, Hasher};
+///
+/// let state = RandomState::with_seed(42);
+/// let mut hasher = state.build_hasher();
+/// (1, "a").serialize(&mut Serializer::new(&mut hasher)).unwrap();
+/// let mut other = state.build_hasher();
+/// (1, "b").serialize(&mut Serializer::new(&mut other)).unwrap();
+/// assert_ne!(hasher.finish(), other.finish());
+/// ```
+pub struct Serializer<'a> {
+    hasher: &'a mut AHasher,
+    /// The hasher as it was when the serializer was created, which the entries of sorted maps are hashed with.
+    initial: AHasher,
+    sort_maps: bool,
+}
+
+impl<'a> Serializer<'a> {
+    /// Creates a serializer which writes to `hasher`. Maps are not sorted.
+    #[inline]
+    pub fn new(hasher: &'a mut AHasher) -> Serializer<'a> {
+        let initial = hasher.clone();
+        Serializer {
+            hasher,
+            initial,
+            sort_maps: false,
+        }
+    }
+
+    /// Sets whether the entries of maps are sorted, so that the order in which they are serialized does not affect
+    /// the result.
+    ///
+    /// Each entry is hashed separately with a copy of the hasher as it was when the serializer was created, and the
+    /// resulting 128 bit hashes are sorted and written to the hasher. This needs to allocate, so it is somewhat slower.
+    #[inline]
+    pub fn sort_maps(mut self, sort_maps: bool) -> Serializer<'a> {
+        self.sort_maps = sort_maps;
+        self
+    }
+
+    /// Creates a serializer for the entry of a sorted map, with the same settings as this one.
+    #[inline]
+    fn nested<'b>(&self, hasher: &'b mut AHasher) -> Serializer<'b> {
+        Serializer {
+            hasher,
+            initial: self.initial.clone(),
+            sort_maps: self.sort_maps,
+        }
+    }
+
+    #[inline]
+    fn tag(&mut self, tag: u8) {
+        self.hasher.write_u8(tag);
+    }
+
+    #[inline]
+    fn write_bytes(&mut self, bytes: &[u8]) {
+        self.hasher.write_u64(bytes.len() as u64);
+        self.hasher.write(bytes);
+    }
+
+    #[inline]
+    fn write_name(&mut self, name: &str) {
+        self.write_bytes(name.as_bytes());
+    }
+}
+
+impl fmt::Debug for Serializer<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Serializer").field("sort_maps", &self.sort_maps).finish()
+    }
+}
+
+impl<'b, 'a> ser::Serializer for &'b mut Serializer<'a> {
+    type Ok = ();
+    type Error = Error;
+    type SerializeSeq = Self;
+    type SerializeTuple = Self;
+    type SerializeTupleStruct = Self;
+    type SerializeTupleVariant = Self;
+    type SerializeMap = MapSerializer<'b, 'a>;
+    type SerializeStruct = Self;
+    type SerializeStructVariant = Self;
+
+    fn serialize_bool(self, v: bool) -> Result<(), Error> {
+        self.tag(tag::BOOL);
+        self.hasher.write_u8(v as u8);
+        Ok(())
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<(), Error> {
+        self.tag(tag::I8);
+        self.hasher.write_i8(v);
+        Ok(())
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<(), Error> {
+        self.tag(tag::I16);
+        self.hasher.write_i16(v);
+        Ok(())
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<(), Error> {
+        self.tag(tag::I32);
+        self.hasher.write_i32(v);
+        Ok(())
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<(), Error> {
+        self.tag(tag::I64);
+        self.hasher.write_i64(v);
+        Ok(())
+    }
+
+    fn serialize_i128(self, v: i128) -> Result<(), Error> {
+        self.tag(tag::I128);
+        self.hasher.write_i128(v);
+        Ok(())
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<(), Error> {
+        self.tag(tag::U8);
+        self.hasher.write_u8(v);
+        Ok(())
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<(), Error> {
+        self.tag(tag::U16);
+        self.hasher.write_u16(v);
+        Ok(())
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<(), Error> {
+        self.tag(tag::U32);
+        self.hasher.write_u32(v);
+        Ok(())
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<(), Error> {
+        self.tag(tag::U64);
+        self.hasher.write_u64(v);
+        Ok(())
+    }
+
+    fn serialize_u128(self, v: u128) -> Result<(), Error> {
+        self.tag(tag::U128);
+        self.hasher.write_u128(v);
+        Ok(())
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<(), Error> {
+        let bits = if v == 0.0 {
+            0
+        } else if v.is_nan() {
+            f32::NAN.to_bits()
+        } else {
+            v.to_bits()
+        };
+        self.tag(tag::F32);
+        self.hasher.write_u32(bits);
+        Ok(())
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<(), Error> {
+        let bits = if v == 0.0 {
+            0
+        } else if v.is_nan() {
+            f64::NAN.to_bits()
+        } else {
+            v.to_bits()
+        };
+        self.tag(tag::F64);
+        self.hasher.write_u64(bits);
+        Ok(())
+    }
+
+    fn serialize_char(self, v: char) -> Result<(), Error> {
+        self.tag(tag::CHAR);
+        self.hasher.write_u32(v as u32);
+        Ok(())
+    }
+
+    fn serialize_str(self, v: &str) -> Result<(), Error> {
+        self.tag(tag::STR);
+        self.write_bytes(v.as_bytes());
+        Ok(())
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<(), Error> {
+        self.tag(tag::BYTES);
+        self.write_bytes(v);
+        Ok(())
+    }
+
+    fn serialize_none(self) -> Result<(), Error> {
+        self.tag(tag::NONE);
+        Ok(())
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<(), Error> {
+        self.tag(tag::SOME);
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<(), Error> {
+        self.tag(tag::UNIT);
+        Ok(())
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<(), Error> {
+        self.tag(tag::UNIT_STRUCT);
+        Ok(())
+    }
+
+    fn serialize_unit_variant(self, _name: &'static str, _index: u32, variant: &'static str) -> Result<(), Error> {
+        self.tag(tag::UNIT_VARIANT);
+        self.write_name(variant);
+        Ok(())
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(self, _name: &'static str, value: &T) -> Result<(), Error> {
+        self.tag(tag::NEWTYPE_STRUCT);
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        self.tag(tag::NEWTYPE_VARIANT);
+        self.write_name(variant);
+        value.serialize(self)
+    }
+
+    // The length is not written, as it is not always known. Instead the end is marked with `END`, which is distinct
+    // from the tag that starts each element.
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self, Error> {
+        self.tag(tag::SEQ);
+        Ok(self)
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self, Error> {
+        self.tag(tag::TUPLE);
+        Ok(self)
+    }
+
+    fn serialize_tuple_struct(self, _name: &'static str, _len: usize) -> Result<Self, Error> {
+        self.tag(tag::TUPLE_STRUCT);
+        Ok(self)
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<Self, Error> {
+        self.tag(tag::TUPLE_VARIANT);
+        self.write_name(variant);
+        Ok(self)
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<MapSerializer<'b, 'a>, Error> {
+        if self.sort_maps {
+            self.tag(tag::SORTED_MAP);
+            Ok(MapSerializer {
+                ser: self,
+                sorted: Some(SortedEntries {
+                    entries: Vec::new(),
+                    entry: None,
+                }),
+            })
+        } else {
+            self.tag(tag::MAP);
+            Ok(MapSerializer { ser: self, sorted: None })
+        }
+    }
+
+    fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<Self, Error> {
+        self.tag(tag::STRUCT);
+        Ok(self)
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<Self, Error> {
+        self.tag(tag::STRUCT_VARIANT);
+        self.write_name(variant);
+        Ok(self)
+    }
+
+    fn is_human_readable(&self) -> bool {
+        false
+    }
+}
+
+impl ser::SerializeSeq for &mut Serializer<'_> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        value.serialize(&mut **self)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        self.tag(tag::END);
+        Ok(())
+    }
+}
+
+impl ser::SerializeTuple for &mut Serializer<'_> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        value.serialize(&mut **self)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        self.tag(tag::END);
+        Ok(())
+    }
+}
+
+impl ser::SerializeTupleStruct for &mut Serializer<'_> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        value.serialize(&mut **self)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        self.tag(tag::END);
+        Ok(())
+    }
+}
+
+impl ser::SerializeTupleVariant for &mut Serializer<'_> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        value.serialize(&mut **self)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        self.tag(tag::END);
+        Ok(())
+    }
+}
+
+impl ser::SerializeStruct for &mut Serializer<'_> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, key: &'static str, value: &T) -> Result<(), Error> {
+        self.tag(tag::FIELD);
+        self.write_name(key);
+        value.serialize(&mut **self)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        self.tag(tag::END);
+        Ok(())
+    }
+}
+
+impl ser::SerializeStructVariant for &mut Serializer<'_> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, key: &'static str, value: &T) -> Result<(), Error> {
+        self.tag(tag::FIELD);
+        self.write_name(key);
+        value.serialize(&mut **self)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        self.tag(tag::END);
+        Ok(())
+    }
+}
+
+/// The entries of a map which are sorted before being written.
+struct SortedEntries {
+    entries: Vec<u128>,
+    /// The hasher for the entry whose key has been written but not its value.
+    entry: Option<AHasher>,
+}
+
+/// Serializes the entries of a map. (Returned by [serde::Serializer::serialize_map] for [Serializer])
+pub struct MapSerializer<'b, 'a> {
+    ser: &'b mut Serializer<'a>,
+    sorted: Option<SortedEntries>,
+}
+
+impl fmt::Debug for MapSerializer<'_, '_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MapSerializer").field("sorted", &self.sorted.is_some()).finish()
+    }
+}
+
+impl ser::SerializeMap for MapSerializer<'_, '_> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), Error> {
+        match &mut self.sorted {
+            Some(sorted) => {
+                let mut hasher = self.ser.initial.clone();
+                key.serialize(&mut self.ser.nested(&mut hasher))?;
+                sorted.entry = Some(hasher);
+                Ok(())
+            }
+            None => key.serialize(&mut *self.ser),
+        }
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        match &mut self.sorted {
+            Some(sorted) => {
+                let mut hasher = match sorted.entry.take() {
+                    Some(hasher) => hasher,
+                    None => return Err(ser::Error::custom("serialize_value called before serialize_key")),
+                };
+                value.serialize(&mut self.ser.nested(&mut hasher))?;
+                sorted.entries.push(hasher.finish_u128());
+                Ok(())
+            }
+            None => value.serialize(&mut *self.ser),
+        }
+    }
+
+    fn end(self) -> Result<(), Error> {
+        if let Some(mut sorted) = self.sorted {
+            sorted.entries.sort_unstable();
+            for entry in sorted.entries {
+                self.ser.hasher.write_u128(entry);
+            }
+        }
+        self.ser.tag(tag::END);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde::Serialize;
+    use std::collections::{BTreeMap, HashMap};
+
+    #[derive(Serialize)]
+    struct Config {
+        name: String,
+        limits: HashMap<String, u32>,
+        ratio: f64,
+        mode: Mode,
+    }
+
+    #[derive(Serialize)]
+    enum Mode {
+        Off,
+        Fixed(u8),
+        Range { low: u8, high: u8 },
+    }
+
+    fn state() -> RandomState {
+        RandomState::with_seeds(1, 2, 3, 4)
+    }
+
+    fn h<T: Serialize + ?Sized>(value: &T) -> u64 {
+        hash(&state(), value).unwrap()
+    }
+
+    fn sorted<T: Serialize + ?Sized>(value: &T) -> u64 {
+        hash_with_sorted_maps(&state(), value).unwrap()
+    }
+
+    #[test]
+    fn test_deterministic() {
+        assert_eq!(h(&(1_u32, "a", [2_u64, 3])), h(&(1_u32, "a", [2_u64, 3])));
+        assert_ne!(h("a"), hash(&RandomState::with_seeds(1, 2, 3, 5), "a").unwrap());
+        assert_eq!(h(&vec![1_u8, 2]), h(&[1_u8, 2][..]));
+    }
+
+    #[test]
+    fn test_type_tagged() {
+        assert_ne!(h(&1_u32), h(&1_u64));
+        assert_ne!(h(&1_u32), h(&1_i32));
+        assert_ne!(h(&1_u32), h(&Some(1_u32)));
+        assert_ne!(h(&None::<u32>), h(&()));
+        assert_ne!(h("a"), h(&'a'));
+        assert_ne!(h(&Mode::Off), h(&Mode::Fixed(0)));
+        assert_ne!(h(&Mode::Fixed(1)), h(&Mode::Range { low: 1, high: 1 }));
+        let empty: [u8; 0] = [];
+        assert_ne!(h(&empty), h(&Vec::<u8>::new()));
+    }
+
+    #[test]
+    fn test_prefix_free() {
+        assert_ne!(h(&("ab", "c")), h(&("a", "bc")));
+        assert_ne!(h(&vec![vec![1_u8], vec![]]), h(&vec![vec![], vec![1_u8]]));
+        assert_ne!(h(&(vec![1_u8, 2], vec![3_u8])), h(&(vec![1_u8], vec![2_u8, 3])));
+        assert_ne!(h(&vec![Some(()), None]), h(&vec![None, Some(())]));
+        assert_ne!(h(&(String::new(), "a")), h(&("a", String::new())));
+    }
+
+    #[test]
+    fn test_floats() {
+        assert_eq!(h(&0.0_f64), h(&-0.0_f64));
+        assert_eq!(h(&f64::NAN), h(&-f64::NAN));
+        assert_eq!(h(&f32::NAN), h(&f32::from_bits(f32::NAN.to_bits() + 1)));
+        assert_ne!(h(&1.0_f64), h(&-1.0_f64));
+        assert_ne!(h(&1.0_f64), h(&1.0_f32));
+    }
+
+    #[test]
+    fn test_sort_maps() {
+        let mut a: HashMap<String, u32> = HashMap::with_hasher(std::collections::hash_map::RandomState::new());
+        let mut b: HashMap<String, u32> = HashMap::with_capacity(1000);
+        for i in 0..100 {
+            a.insert(i.to_string(), i);
+            b.insert((99 - i).to_string(), 99 - i);
+        }
+        let ordered: BTreeMap<String, u32> = a.iter().map(|(k, v)| (k.clone(), *v)).collect();
+        assert_eq!(sorted(&a), sorted(&b));
+        assert_eq!(sorted(&a), sorted(&ordered));
+        assert_ne!(h(&ordered), sorted(&ordered));
+
+        b.insert("1".to_string(), 2);
+        assert_ne!(sorted(&a), sorted(&b));
+        // Swapping values between keys changes the hash.
+        let swapped: BTreeMap<_, _> = vec![("1".to_string(), 2_u32), ("2".to_string(), 1)].into_iter().collect();
+        let unswapped: BTreeMap<_, _> = vec![("1".to_string(), 1_u32), ("2".to_string(), 2)].into_iter().collect();
+        assert_ne!(sorted(&swapped), sorted(&unswapped));
+    }
+
+    #[test]
+    fn test_nested_struct() {
+        let config = |limits: HashMap<String, u32>| Config {
+            name: "test".to_string(),
+            limits,
+            ratio: 0.5,
+            mode: Mode::Range { low: 1, high: 2 },
+        };
+        let mut a = HashMap::new();
+        let mut b = HashMap::new();
+        for i in 0..50 {
+            a.insert(format!("key{}", i), i);
+            b.insert(format!("key{}", 49 - i), 49 - i);
+        }
+        let other = HashMap::from([("key1".to_string(), 1)]);
+        assert_eq!(sorted(&config(a.clone())), sorted(&config(b)));
+        assert_ne!(sorted(&config(a)), sorted(&config(other)));
+    }
+
+    #[test]
+    fn test_error() {
+        use std::sync::{Arc, Mutex};
+
+        let mutex = Arc::new(Mutex::new(1_u32));
+        assert_eq!(h(&*mutex), h(&1_u32));
+        let poisoned = mutex.clone();
+        let _ = std::thread::spawn(move || {
+            let _guard = poisoned.lock().unwrap();
+            panic!("poison the mutex");
+        })
+        .join();
+        let error = hash(&state(), &*mutex).unwrap_err();
+        assert!(!error.to_string().is_empty());
+    }
+}
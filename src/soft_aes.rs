@@ -0,0 +1,204 @@
+//! A software implementation of a single AES round.
+//!
+//! This produces exactly the same result as the x86 `_mm_aesenc_si128` and `_mm_aesdec_si128` instructions, so the
+//! AES based hasher can be run (and tested) where those instructions are not available, such as under Miri.
+//!
+//! The implementation is constant time: it does not use lookup tables or branch on the data. Instead the S-box is
+//! computed arithmetically for all 16 bytes at once by treating the `u128` as 16 independent lanes of GF(2^8).
+//! This makes it considerably slower than the hardware instructions.
+use crate::convert::*;
+
+const LOW_BITS: u128 = 0x0101_0101_0101_0101_0101_0101_0101_0101;
+const HIGH_BITS: u128 = LOW_BITS << 7;
+
+/// Multiplies each byte by `x` in GF(2^8) (with the AES polynomial).
+#[inline(always)]
+fn xtime(a: u128) -> u128 {
+    let carry = (a & HIGH_BITS) >> 7;
+    // Multiplying each carry bit by 0x1b without a multiply instruction.
+    let reduce = carry ^ (carry << 1) ^ (carry << 3) ^ (carry << 4);
+    ((a & !HIGH_BITS) << 1) ^ reduce
+}
+
+/// Multiplies each pair of corresponding bytes in GF(2^8).
+#[inline(always)]
+fn gf_mul(mut a: u128, b: u128) -> u128 {
+    let mut result = 0;
+    for bit in 0..8 {
+        let mask = ((b >> bit) & LOW_BITS).wrapping_mul(0xff);
+        result ^= a & mask;
+        a = xtime(a);
+    }
+    result
+}
+
+/// Computes the multiplicative inverse of each byte in GF(2^8) as `a^254`. (Zero maps to zero)
+#[inline(always)]
+fn gf_inverse(a: u128) -> u128 {
+    let a2 = gf_mul(a, a);
+    let a3 = gf_mul(a2, a);
+    let a6 = gf_mul(a3, a3);
+    let a12 = gf_mul(a6, a6);
+    let a15 = gf_mul(a12, a3);
+    let a30 = gf_mul(a15, a15);
+    let a60 = gf_mul(a30, a30);
+    let a120 = gf_mul(a60, a60);
+    let a126 = gf_mul(a120, a6);
+    let a127 = gf_mul(a126, a);
+    gf_mul(a127, a127)
+}
+
+/// Rotates each byte left by `n` bits.
+#[inline(always)]
+fn rotate_bytes(a: u128, n: u32) -> u128 {
+    let low_mask = LOW_BITS.wrapping_mul((1 << n) - 1);
+    ((a << n) & !low_mask) | ((a >> (8 - n)) & low_mask)
+}
+
+#[inline(always)]
+fn sub_bytes(a: u128) -> u128 {
+    let b = gf_inverse(a);
+    b ^ rotate_bytes(b, 1) ^ rotate_bytes(b, 2) ^ rotate_bytes(b, 3) ^ rotate_bytes(b, 4) ^ (LOW_BITS * 0x63)
+}
+
+#[inline(always)]
+fn inv_sub_bytes(a: u128) -> u128 {
+    let b = rotate_bytes(a, 1) ^ rotate_bytes(a, 3) ^ rotate_bytes(a, 6) ^ (LOW_BITS * 0x05);
+    gf_inverse(b)
+}
+
+/// The state is stored in column major order, so byte `row + 4 * column` is at that row and column.
+#[inline(always)]
+fn shift_rows(state: [u8; 16]) -> [u8; 16] {
+    let mut result = [0; 16];
+    for column in 0..4 {
+        for row in 0..4 {
+            result[row + 4 * column] = state[row + 4 * ((column + row) % 4)];
+        }
+    }
+    result
+}
+
+#[inline(always)]
+fn inv_shift_rows(state: [u8; 16]) -> [u8; 16] {
+    let mut result = [0; 16];
+    for column in 0..4 {
+        for row in 0..4 {
+            result[row + 4 * ((column + row) % 4)] = state[row + 4 * column];
+        }
+    }
+    result
+}
+
+#[inline(always)]
+fn mix_columns(state: u128) -> u128 {
+    let x1: [u8; 16] = state.convert();
+    let x2: [u8; 16] = xtime(state).convert();
+    let mut result = [0; 16];
+    for column in 0..4 {
+        let c = 4 * column;
+        for row in 0..4 {
+            let (r1, r2, r3) = (c + (row + 1) % 4, c + (row + 2) % 4, c + (row + 3) % 4);
+            result[c + row] = x2[c + row] ^ x2[r1] ^ x1[r1] ^ x1[r2] ^ x1[r3];
+        }
+    }
+    result.convert()
+}
+
+#[inline(always)]
+fn inv_mix_columns(state: u128) -> u128 {
+    let x1: [u8; 16] = state.convert();
+    let x2 = xtime(state);
+    let x4 = xtime(x2);
+    let x8: [u8; 16] = xtime(x4).convert();
+    let x2: [u8; 16] = x2.convert();
+    let x4: [u8; 16] = x4.convert();
+    let mut result = [0; 16];
+    for column in 0..4 {
+        let c = 4 * column;
+        for row in 0..4 {
+            let (r0, r1, r2, r3) = (c + row, c + (row + 1) % 4, c + (row + 2) % 4, c + (row + 3) % 4);
+            // 14 * r0 ^ 11 * r1 ^ 13 * r2 ^ 9 * r3
+            result[r0] = (x8[r0] ^ x4[r0] ^ x2[r0])
+                ^ (x8[r1] ^ x2[r1] ^ x1[r1])
+                ^ (x8[r2] ^ x4[r2] ^ x1[r2])
+                ^ (x8[r3] ^ x1[r3]);
+        }
+    }
+    result.convert()
+}
+
+/// Performs one round of AES encryption. The same as `_mm_aesenc_si128(value, xor)`.
+#[allow(unused)]
+#[inline]
+pub(crate) fn aesenc(value: u128, xor: u128) -> u128 {
+    let shifted: u128 = shift_rows(sub_bytes(value).convert()).convert();
+    mix_columns(shifted) ^ xor
+}
+
+/// Performs one round of AES decryption. The same as `_mm_aesdec_si128(value, xor)`.
+#[allow(unused)]
+#[inline]
+pub(crate) fn aesdec(value: u128, xor: u128) -> u128 {
+    let shifted: u128 = inv_shift_rows(value.convert()).convert();
+    inv_mix_columns(inv_sub_bytes(shifted)) ^ xor
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Example values from Intel's "Advanced Encryption Standard (AES) New Instructions Set" white paper.
+    #[test]
+    fn test_known_values() {
+        let state = 0x7b5b5465_73745665_63746f72_5d53475d_u128;
+        let key = 0x48692853_68617929_5b477565_726f6e5d_u128;
+        assert_eq!(0xa8311c2f_9fdba3c5_8b104b58_ded7e595_u128, aesenc(state, key));
+        assert_eq!(0x138ac342_faea2787_b58eb95e_b730392a_u128, aesdec(state, key));
+    }
+
+    #[test]
+    fn test_sbox() {
+        // Spot checks from FIPS-197 figure 7 and figure 14.
+        let input: [u8; 16] = [0x00, 0x01, 0x53, 0xff, 0x10, 0x7c, 0xca, 0x63, 0, 0, 0, 0, 0, 0, 0, 0];
+        let output: [u8; 16] = sub_bytes(input.convert()).convert();
+        assert_eq!([0x63, 0x7c, 0xed, 0x16, 0xca, 0x10, 0x74, 0xfb], output[..8]);
+        for i in 0..=255_u8 {
+            let value: u128 = [i; 16].convert();
+            assert_eq!(value, inv_sub_bytes(sub_bytes(value)), "Byte {}", i);
+        }
+    }
+
+    #[test]
+    fn test_rows_and_columns_invert() {
+        let value = 0x00112233_44556677_8899aabb_ccddeeff_u128;
+        let shifted: [u8; 16] = shift_rows(value.convert());
+        assert_eq!(value, inv_shift_rows(shifted).convert());
+        assert_eq!(value, inv_mix_columns(mix_columns(value)));
+    }
+
+    #[cfg(all(any(target_arch = "x86", target_arch = "x86_64"), target_feature = "aes", not(miri)))]
+    #[test]
+    fn test_matches_hardware() {
+        #[cfg(target_arch = "x86")]
+        use core::arch::x86::*;
+        #[cfg(target_arch = "x86_64")]
+        use core::arch::x86_64::*;
+        use core::mem::transmute;
+        use rand::Rng;
+
+        let mut rng = rand::thread_rng();
+        for _ in 0..10_000 {
+            let value: u128 = rng.gen();
+            let xor: u128 = rng.gen();
+            let (enc, dec): (u128, u128) = unsafe {
+                (
+                    transmute(_mm_aesenc_si128(transmute(value), transmute(xor))),
+                    transmute(_mm_aesdec_si128(transmute(value), transmute(xor))),
+                )
+            };
+            assert_eq!(enc, aesenc(value, xor), "aesenc({:x}, {:x})", value, xor);
+            assert_eq!(dec, aesdec(value, xor), "aesdec({:x}, {:x})", value, xor);
+        }
+    }
+}
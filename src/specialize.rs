@@ -3,6 +3,9 @@ use core::hash::BuildHasher;
 use core::hash::Hash;
 use core::hash::Hasher;
 
+use crate::operations::hash_by_4;
+use crate::RandomState;
+
 #[cfg(not(feature = "std"))]
 extern crate alloc;
 #[cfg(feature = "std")]
@@ -11,7 +14,15 @@ extern crate std as alloc;
 #[cfg(feature = "specialize")]
 use crate::BuildHasherExt;
 #[cfg(feature = "specialize")]
+use alloc::borrow::Cow;
+#[cfg(feature = "specialize")]
+use alloc::boxed::Box;
+#[cfg(feature = "specialize")]
+use alloc::rc::Rc;
+#[cfg(feature = "specialize")]
 use alloc::string::String;
+#[cfg(all(feature = "specialize", target_has_atomic = "ptr"))]
+use alloc::sync::Arc;
 #[cfg(feature = "specialize")]
 use alloc::vec::Vec;
 
@@ -132,6 +143,145 @@ impl CallHasher for String {
     }
 }
 
+call_hasher_impl!(char);
+call_hasher_impl!(bool);
+
+// Tuples of small primitives only write each of their elements, which are at most 64 bits.
+call_hasher_impl!((u8, u8));
+call_hasher_impl!((u16, u16));
+call_hasher_impl!((u32, u32));
+call_hasher_impl!((u64, u64));
+call_hasher_impl!((i8, i8));
+call_hasher_impl!((i16, i16));
+call_hasher_impl!((i32, i32));
+call_hasher_impl!((i64, i64));
+call_hasher_impl!((u32, u64));
+call_hasher_impl!((u64, u32));
+
+/// For types which write a fixed number of primitives, some of which may be larger than 64 bits.
+/// This includes `Option`s, because the discriminant is written as an `isize`.
+macro_rules! call_hasher_fixed_impl {
+    ($typ:ty) => {
+        #[cfg(feature = "specialize")]
+        impl CallHasher for $typ {
+            #[inline]
+            fn get_hash<H: Hash + ?Sized, B: BuildHasher>(value: &H, build_hasher: &B) -> u64 {
+                build_hasher.hash_as_fixed_length(value)
+            }
+        }
+    };
+}
+call_hasher_fixed_impl!((u128, u128));
+call_hasher_fixed_impl!((usize, usize));
+call_hasher_fixed_impl!(Option<u8>);
+call_hasher_fixed_impl!(Option<u16>);
+call_hasher_fixed_impl!(Option<u32>);
+call_hasher_fixed_impl!(Option<u64>);
+call_hasher_fixed_impl!(Option<u128>);
+call_hasher_fixed_impl!(Option<usize>);
+call_hasher_fixed_impl!(Option<i8>);
+call_hasher_fixed_impl!(Option<i16>);
+call_hasher_fixed_impl!(Option<i32>);
+call_hasher_fixed_impl!(Option<i64>);
+call_hasher_fixed_impl!(Option<i128>);
+call_hasher_fixed_impl!(Option<isize>);
+call_hasher_fixed_impl!(Option<char>);
+call_hasher_fixed_impl!(Option<bool>);
+
+/// For types which hash the same way as a single `str` or `[u8]`.
+macro_rules! call_hasher_str_impl {
+    ($typ:ty) => {
+        #[cfg(feature = "specialize")]
+        impl CallHasher for $typ {
+            #[inline]
+            fn get_hash<H: Hash + ?Sized, B: BuildHasher>(value: &H, build_hasher: &B) -> u64 {
+                build_hasher.hash_as_str(value)
+            }
+        }
+    };
+}
+call_hasher_str_impl!(Box<str>);
+call_hasher_str_impl!(Rc<str>);
+call_hasher_str_impl!(Cow<'_, str>);
+call_hasher_str_impl!(Box<[u8]>);
+call_hasher_str_impl!(Rc<[u8]>);
+call_hasher_str_impl!(Cow<'_, [u8]>);
+#[cfg(target_has_atomic = "ptr")]
+call_hasher_str_impl!(Arc<str>);
+#[cfg(target_has_atomic = "ptr")]
+call_hasher_str_impl!(Arc<[u8]>);
+
+/// Arrays hash the same way as the equivalent slice.
+#[cfg(feature = "specialize")]
+impl<const N: usize> CallHasher for [u8; N] {
+    #[inline]
+    fn get_hash<H: Hash + ?Sized, B: BuildHasher>(value: &H, build_hasher: &B) -> u64 {
+        build_hasher.hash_as_str(value)
+    }
+}
+
+/// Provides a way to hash a batch of values of a given type, which gives the same hashes as [CallHasher].
+/// Where [CallHasher] has an optimized hasher for the type, this uses a kernel which hashes four values in lockstep.
+pub(crate) trait CallHasherMany: Sized {
+    fn get_hashes(values: &[Self], build_hasher: &RandomState, hashes: &mut [u64]);
+}
+
+#[cfg(not(feature = "specialize"))]
+impl<T: Hash> CallHasherMany for T {
+    #[inline]
+    fn get_hashes(values: &[Self], build_hasher: &RandomState, hashes: &mut [u64]) {
+        hash_by_4(build_hasher, values, hashes)
+    }
+}
+
+#[cfg(feature = "specialize")]
+impl<T: Hash> CallHasherMany for T {
+    #[inline]
+    default fn get_hashes(values: &[Self], build_hasher: &RandomState, hashes: &mut [u64]) {
+        hash_by_4(build_hasher, values, hashes)
+    }
+}
+
+#[cfg(feature = "specialize")]
+impl CallHasherMany for u32 {
+    #[inline]
+    fn get_hashes(values: &[Self], build_hasher: &RandomState, hashes: &mut [u64]) {
+        build_hasher.hash_many_as_u64(values, hashes)
+    }
+}
+
+#[cfg(feature = "specialize")]
+impl CallHasherMany for u64 {
+    #[inline]
+    fn get_hashes(values: &[Self], build_hasher: &RandomState, hashes: &mut [u64]) {
+        build_hasher.hash_many_as_u64(values, hashes)
+    }
+}
+
+#[cfg(feature = "specialize")]
+impl CallHasherMany for u128 {
+    #[inline]
+    fn get_hashes(values: &[Self], build_hasher: &RandomState, hashes: &mut [u64]) {
+        build_hasher.hash_many_as_fixed_length(values, hashes)
+    }
+}
+
+#[cfg(feature = "specialize")]
+impl CallHasherMany for String {
+    #[inline]
+    fn get_hashes(values: &[Self], build_hasher: &RandomState, hashes: &mut [u64]) {
+        build_hasher.hash_many_as_str(values, hashes)
+    }
+}
+
+#[cfg(feature = "specialize")]
+impl CallHasherMany for Vec<u8> {
+    #[inline]
+    fn get_hashes(values: &[Self], build_hasher: &RandomState, hashes: &mut [u64]) {
+        build_hasher.hash_many_as_str(values, hashes)
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -216,4 +366,156 @@ mod test {
             <[u8]>::get_hash(&"test".to_string().into_bytes(), &build_hasher)
         );
     }
+
+    #[test]
+    pub fn test_ref_independent_compound() {
+        use alloc::borrow::Cow;
+        use alloc::rc::Rc;
+        use alloc::sync::Arc;
+
+        let build_hasher = RandomState::with_seeds(1, 2, 3, 4);
+        assert_eq!(char::get_hash(&&'a', &build_hasher), char::get_hash(&'a', &build_hasher));
+        assert_eq!(bool::get_hash(&&true, &build_hasher), bool::get_hash(&true, &build_hasher));
+        assert_eq!(
+            <(u32, u32)>::get_hash(&&(1, 2), &build_hasher),
+            <(u32, u32)>::get_hash(&(1, 2), &build_hasher)
+        );
+        assert_eq!(
+            <(u128, u128)>::get_hash(&&(1, 2), &build_hasher),
+            <(u128, u128)>::get_hash(&(1, 2), &build_hasher)
+        );
+        assert_eq!(
+            <Option<u64>>::get_hash(&&Some(3), &build_hasher),
+            <Option<u64>>::get_hash(&Some(3), &build_hasher)
+        );
+        assert_eq!(
+            <Option<u64>>::get_hash(&&None::<u64>, &build_hasher),
+            <Option<u64>>::get_hash(&None::<u64>, &build_hasher)
+        );
+        assert_eq!(
+            <[u8; 16]>::get_hash(&&[7; 16], &build_hasher),
+            <[u8; 16]>::get_hash(&[7; 16], &build_hasher)
+        );
+
+        // Types which can be borrowed as each other must hash the same.
+        let expected = str::get_hash("test", &build_hasher);
+        let boxed: Box<str> = "test".into();
+        assert_eq!(<Box<str>>::get_hash(&boxed, &build_hasher), expected);
+        assert_eq!(<Box<str>>::get_hash(&&boxed, &build_hasher), expected);
+        let rc: Rc<str> = "test".into();
+        assert_eq!(<Rc<str>>::get_hash(&rc, &build_hasher), expected);
+        let arc: Arc<str> = "test".into();
+        assert_eq!(<Arc<str>>::get_hash(&arc, &build_hasher), expected);
+        assert_eq!(<Arc<str>>::get_hash(&&arc, &build_hasher), expected);
+        let cow: Cow<str> = Cow::Borrowed("test");
+        assert_eq!(<Cow<str>>::get_hash(&cow, &build_hasher), expected);
+        let cow: Cow<str> = Cow::Owned("test".to_string());
+        assert_eq!(<Cow<str>>::get_hash(&&cow, &build_hasher), expected);
+
+        let bytes = [1_u8, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16];
+        let expected = <[u8]>::get_hash(&bytes[..], &build_hasher);
+        assert_eq!(<[u8; 16]>::get_hash(&bytes, &build_hasher), expected);
+        assert_eq!(<Vec<u8>>::get_hash(&bytes.to_vec(), &build_hasher), expected);
+        let boxed: Box<[u8]> = bytes.into();
+        assert_eq!(<Box<[u8]>>::get_hash(&boxed, &build_hasher), expected);
+        let arc: Arc<[u8]> = bytes.into();
+        assert_eq!(<Arc<[u8]>>::get_hash(&&arc, &build_hasher), expected);
+    }
+
+    /// Checks that `hash_one` gives each value the hash its `Hash` impl produces with the hasher [CallHasher] uses
+    /// for the type, which is `$kind` with specialization and the general [AHasher] without it.
+    macro_rules! assert_hash_one_matches_hash_impl {
+        ($build_hasher:expr, $kind:ident, $($value:expr),+ $(,)?) => {$(
+            let value = $value;
+            let expected = if cfg!(feature = "specialize") {
+                $build_hasher.$kind(&value)
+            } else {
+                let mut hasher = $build_hasher.build_hasher();
+                value.hash(&mut hasher);
+                hasher.finish()
+            };
+            assert_eq!($build_hasher.hash_one(value.clone()), expected, "{:?}", value);
+        )+};
+    }
+
+    #[test]
+    pub fn test_compound_hash_one_matches_hash_impl() {
+        use alloc::borrow::Cow;
+        use alloc::rc::Rc;
+        use alloc::sync::Arc;
+
+        let build_hasher = RandomState::with_seeds(1, 2, 3, 4);
+        assert_hash_one_matches_hash_impl!(build_hasher, hash_as_u64, 'a', '\u{10ffff}', true, false);
+        assert_hash_one_matches_hash_impl!(
+            build_hasher,
+            hash_as_u64,
+            (1_u8, 2_u8),
+            (1_u16, 2_u16),
+            (1_u32, 2_u32),
+            (1_u64, 2_u64),
+            (-1_i8, 2_i8),
+            (-1_i16, 2_i16),
+            (-1_i32, 2_i32),
+            (-1_i64, 2_i64),
+            (1_u32, 2_u64),
+            (1_u64, 2_u32),
+        );
+        assert_hash_one_matches_hash_impl!(build_hasher, hash_as_fixed_length, (1_u128, 2_u128), (1_usize, 2_usize));
+        assert_hash_one_matches_hash_impl!(
+            build_hasher,
+            hash_as_fixed_length,
+            Some(1_u8),
+            Some(1_u16),
+            Some(1_u32),
+            Some(1_u64),
+            Some(1_u128),
+            Some(1_usize),
+            Some(-1_i8),
+            Some(-1_i16),
+            Some(-1_i32),
+            Some(-1_i64),
+            Some(-1_i128),
+            Some(-1_isize),
+            Some('a'),
+            Some(true),
+            None::<u64>,
+            None::<char>,
+        );
+
+        let boxed: Box<str> = "test".into();
+        let rc: Rc<str> = "test".into();
+        let arc: Arc<str> = "test".into();
+        let cow: Cow<str> = Cow::Borrowed("test");
+        assert_hash_one_matches_hash_impl!(build_hasher, hash_as_str, boxed, rc, arc, cow);
+        let bytes = [1_u8, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16];
+        let boxed: Box<[u8]> = bytes.into();
+        let rc: Rc<[u8]> = bytes.into();
+        let arc: Arc<[u8]> = bytes.into();
+        let cow: Cow<[u8]> = Cow::Owned(bytes.to_vec());
+        assert_hash_one_matches_hash_impl!(build_hasher, hash_as_str, boxed, rc, arc, cow);
+        assert_hash_one_matches_hash_impl!(build_hasher, hash_as_str, bytes, [0_u8; 0], [7_u8; 3], [7_u8; 33]);
+    }
+
+    #[test]
+    pub fn test_compound_distinct() {
+        let build_hasher = RandomState::with_seeds(1, 2, 3, 4);
+        assert_ne!(char::get_hash(&'a', &build_hasher), char::get_hash(&'b', &build_hasher));
+        assert_ne!(bool::get_hash(&true, &build_hasher), bool::get_hash(&false, &build_hasher));
+        assert_ne!(
+            <(u32, u32)>::get_hash(&(1, 2), &build_hasher),
+            <(u32, u32)>::get_hash(&(2, 1), &build_hasher)
+        );
+        assert_ne!(
+            <Option<u64>>::get_hash(&Some(0), &build_hasher),
+            <Option<u64>>::get_hash(&None::<u64>, &build_hasher)
+        );
+        assert_ne!(
+            <Option<u64>>::get_hash(&Some(1), &build_hasher),
+            <Option<u64>>::get_hash(&Some(2), &build_hasher)
+        );
+        assert_ne!(
+            <[u8; 16]>::get_hash(&[1; 16], &build_hasher),
+            <[u8; 16]>::get_hash(&[2; 16], &build_hasher)
+        );
+    }
 }
\ No newline at end of file
@@ -0,0 +1,415 @@
+//! Hashers whose output is fixed and does not depend on the platform.
+//!
+//! The output of [AHasher](crate::AHasher) is allowed to change between versions of aHash, between CPUs, and
+//! depending on whether AES instructions are available. This makes it unsuitable for values which are persisted or
+//! shared between machines. The hashers in this module do not have that limitation: each version (such as [v1])
+//! produces exactly the same output for the same keys and sequence of `write` calls regardless of the version of
+//! aHash, the endianness or pointer width of the target, or the instructions the CPU supports.
+//!
+//! Once published, the output of a version will never change. If the algorithm is improved a new version will be
+//! added alongside it.
+//!
+//! Note that this only covers the hasher itself. The [Hash](core::hash::Hash) implementations of types outside of
+//! this crate decide which `write` calls are made. (For example `usize` is hashed as a `u64` here, but a type which
+//! hashes its length differently on different platforms will still produce different output.)
+//!
+//! These hashers do not use AES instructions, so they are somewhat slower than [AHasher](crate::AHasher).
+
+/// Version 1 of the stable hash.
+///
+/// The algorithm is that of the fallback hasher, with all values read as little-endian and always using a 128 bit
+/// multiply.
+///
+/// # Example
+/// ```
+/// use ahash::stable::v1::StableRandomState;
+///
+/// let state = StableRandomState::with_seeds(1, 2, 3, 4);
+/// assert_eq!(state.hash_one("Hello"), StableRandomState::with_seeds(1, 2, 3, 4).hash_one("Hello"));
+/// ```
+pub mod v1 {
+    use core::hash::{BuildHasher, Hash, Hasher};
+
+    // These are copies of the constants used by the rest of the crate. They are duplicated here so that
+    // changes elsewhere can never alter the output of this version.
+    const MULTIPLE: u64 = 6364136223846793005;
+    const ROT: u32 = 23;
+
+    const PI: [u64; 4] = [
+        0x243f_6a88_85a3_08d3,
+        0x1319_8a2e_0370_7344,
+        0xa409_3822_299f_31d0,
+        0x082e_fa98_ec4e_6c89,
+    ];
+
+    const PI2: [u64; 4] = [
+        0x4528_21e6_38d0_1377,
+        0xbe54_66cf_34e9_0c6c,
+        0xc0ac_29b7_c97c_50dd,
+        0x3f84_d5b5_b547_0917,
+    ];
+
+    /// The same as `operations::folded_multiply` when the `folded_multiply` feature is enabled.
+    /// This needs to be independent of the target so it is always computed with a 128 bit multiply.
+    #[inline(always)]
+    const fn folded_multiply(s: u64, by: u64) -> u64 {
+        let result = (s as u128).wrapping_mul(by as u128);
+        ((result & 0xffff_ffff_ffff_ffff) as u64) ^ ((result >> 64) as u64)
+    }
+
+    #[inline(always)]
+    const fn read_u32(data: &[u8], offset: usize) -> u64 {
+        u32::from_le_bytes([data[offset], data[offset + 1], data[offset + 2], data[offset + 3]]) as u64
+    }
+
+    #[inline(always)]
+    const fn read_u64(data: &[u8], offset: usize) -> u64 {
+        u64::from_le_bytes([
+            data[offset],
+            data[offset + 1],
+            data[offset + 2],
+            data[offset + 3],
+            data[offset + 4],
+            data[offset + 5],
+            data[offset + 6],
+            data[offset + 7],
+        ])
+    }
+
+    /// The same as `operations::read_small`, but reading values as little-endian.
+    #[inline(always)]
+    const fn read_small(data: &[u8]) -> [u64; 2] {
+        debug_assert!(data.len() <= 8);
+        if data.len() >= 4 {
+            [read_u32(data, 0), read_u32(data, data.len() - 4)]
+        } else if data.len() >= 2 {
+            [u16::from_le_bytes([data[0], data[1]]) as u64, data[data.len() - 1] as u64]
+        } else if !data.is_empty() {
+            [data[0] as u64, data[0] as u64]
+        } else {
+            [0, 0]
+        }
+    }
+
+    // The steps of the algorithm are `const fn`s on the hasher's fields, so that [hash_bytes_const] and
+    // [hash_str_const] share them with [StableAHasher].
+
+    #[inline(always)]
+    const fn update(buffer: u64, new_data: u64) -> u64 {
+        folded_multiply(new_data ^ buffer, MULTIPLE)
+    }
+
+    #[inline(always)]
+    const fn large_update(buffer: u64, pad: u64, extra_keys: [u64; 2], low: u64, high: u64) -> u64 {
+        let combined = folded_multiply(low ^ extra_keys[0], high ^ extra_keys[1]);
+        (buffer.wrapping_add(pad) ^ combined).rotate_left(ROT)
+    }
+
+    #[inline(always)]
+    const fn write_bytes(buffer: u64, pad: u64, extra_keys: [u64; 2], data: &[u8]) -> u64 {
+        let len = data.len();
+        let mut buffer = buffer.wrapping_add(len as u64).wrapping_mul(MULTIPLE);
+        if len > 8 {
+            if len > 16 {
+                buffer = large_update(buffer, pad, extra_keys, read_u64(data, len - 16), read_u64(data, len - 8));
+                let mut offset = 0;
+                while len - offset > 16 {
+                    buffer = large_update(buffer, pad, extra_keys, read_u64(data, offset), read_u64(data, offset + 8));
+                    offset += 16;
+                }
+            } else {
+                buffer = large_update(buffer, pad, extra_keys, read_u64(data, 0), read_u64(data, len - 8));
+            }
+        } else {
+            let [low, high] = read_small(data);
+            buffer = large_update(buffer, pad, extra_keys, low, high);
+        }
+        buffer
+    }
+
+    #[inline(always)]
+    const fn finish(buffer: u64, pad: u64) -> u64 {
+        let rot = (buffer & 63) as u32;
+        folded_multiply(buffer, pad).rotate_left(rot)
+    }
+
+    /// A `Hasher` whose output is stable across versions and platforms.
+    ///
+    /// Instances are normally created from a [StableRandomState].
+    #[derive(Debug, Clone)]
+    pub struct StableAHasher {
+        buffer: u64,
+        pad: u64,
+        extra_keys: [u64; 2],
+    }
+
+    impl StableAHasher {
+        #[inline(always)]
+        fn update(&mut self, new_data: u64) {
+            self.buffer = update(self.buffer, new_data);
+        }
+
+        #[inline(always)]
+        fn large_update(&mut self, low: u64, high: u64) {
+            self.buffer = large_update(self.buffer, self.pad, self.extra_keys, low, high);
+        }
+    }
+
+    /// Provides [Hasher] methods to hash all of the primitive types.
+    /// `usize` and `isize` are always hashed as 64 bit values.
+    impl Hasher for StableAHasher {
+        #[inline]
+        fn write_u8(&mut self, i: u8) {
+            self.update(i as u64);
+        }
+
+        #[inline]
+        fn write_u16(&mut self, i: u16) {
+            self.update(i as u64);
+        }
+
+        #[inline]
+        fn write_u32(&mut self, i: u32) {
+            self.update(i as u64);
+        }
+
+        #[inline]
+        fn write_u64(&mut self, i: u64) {
+            self.update(i);
+        }
+
+        #[inline]
+        fn write_u128(&mut self, i: u128) {
+            self.large_update(i as u64, (i >> 64) as u64);
+        }
+
+        #[inline]
+        fn write_usize(&mut self, i: usize) {
+            self.write_u64(i as u64);
+        }
+
+        #[inline]
+        fn write(&mut self, input: &[u8]) {
+            self.buffer = write_bytes(self.buffer, self.pad, self.extra_keys, input);
+        }
+
+        #[inline]
+        fn finish(&self) -> u64 {
+            finish(self.buffer, self.pad)
+        }
+    }
+
+    /// Provides a [StableAHasher] factory.
+    ///
+    /// Unlike [RandomState](crate::RandomState) the keys are never random. They must be supplied explicitly
+    /// (or the fixed keys of [Default] used), so that the same keys can be used wherever the hashes are compared.
+    #[derive(Clone, Debug, PartialEq, Eq)]
+    pub struct StableRandomState {
+        k0: u64,
+        k1: u64,
+        k2: u64,
+        k3: u64,
+    }
+
+    impl StableRandomState {
+        /// Creates a state from the provided seeds.
+        /// All `StableRandomState`s created with the same seeds produce identical hashers on every platform.
+        ///
+        /// Note: If DOS resistance is desired one of these should be a secret high quality random number.
+        #[inline]
+        pub const fn with_seeds(k0: u64, k1: u64, k2: u64, k3: u64) -> StableRandomState {
+            StableRandomState {
+                k0: k0 ^ PI2[0],
+                k1: k1 ^ PI2[1],
+                k2: k2 ^ PI2[2],
+                k3: k3 ^ PI2[3],
+            }
+        }
+
+        /// Calculates the hash of a single value.
+        /// This is the same as building a hasher, passing the value to it, and calling `finish()`.
+        #[inline]
+        #[allow(clippy::manual_hash_one)] // `BuildHasher::hash_one` requires Rust 1.71
+        pub fn hash_one<T: Hash>(&self, x: T) -> u64 {
+            let mut hasher = self.build_hasher();
+            x.hash(&mut hasher);
+            hasher.finish()
+        }
+    }
+
+    /// Creates a state using fixed keys. These are the same for every process and platform.
+    impl Default for StableRandomState {
+        #[inline]
+        fn default() -> Self {
+            StableRandomState {
+                k0: PI[0],
+                k1: PI[1],
+                k2: PI[2],
+                k3: PI[3],
+            }
+        }
+    }
+
+    impl BuildHasher for StableRandomState {
+        type Hasher = StableAHasher;
+
+        #[inline]
+        fn build_hasher(&self) -> StableAHasher {
+            StableAHasher {
+                buffer: self.k0,
+                pad: self.k1,
+                extra_keys: [self.k2, self.k3],
+            }
+        }
+    }
+
+    /// Hashes a byte slice in a `const` context.
+    ///
+    /// The result is the same as `StableRandomState::with_seeds(seed[0], seed[1], seed[2], seed[3]).hash_one(data)`,
+    /// so it can be used to build lookup tables at compile time which are then queried with a [StableRandomState].
+    ///
+    /// # Example
+    /// ```
+    /// use ahash::stable::v1::{hash_bytes_const, StableRandomState};
+    ///
+    /// const SEED: [u64; 4] = [1, 2, 3, 4];
+    /// const GET: u64 = hash_bytes_const(SEED, b"GET");
+    /// const PUT: u64 = hash_bytes_const(SEED, b"PUT");
+    ///
+    /// let state = StableRandomState::with_seeds(SEED[0], SEED[1], SEED[2], SEED[3]);
+    /// let method: &[u8] = b"PUT";
+    /// match state.hash_one(method) {
+    ///     GET => panic!("Not a GET"),
+    ///     PUT => {}
+    ///     _ => panic!("Unknown method"),
+    /// }
+    /// ```
+    pub const fn hash_bytes_const(seed: [u64; 4], data: &[u8]) -> u64 {
+        let state = StableRandomState::with_seeds(seed[0], seed[1], seed[2], seed[3]);
+        // `Hash` for slices writes the length first.
+        let buffer = update(state.k0, data.len() as u64);
+        let buffer = write_bytes(buffer, state.k1, [state.k2, state.k3], data);
+        finish(buffer, state.k1)
+    }
+
+    /// Hashes a string in a `const` context.
+    ///
+    /// The result is the same as `StableRandomState::with_seeds(seed[0], seed[1], seed[2], seed[3]).hash_one(data)`.
+    /// (See [hash_bytes_const])
+    pub const fn hash_str_const(seed: [u64; 4], data: &str) -> u64 {
+        let state = StableRandomState::with_seeds(seed[0], seed[1], seed[2], seed[3]);
+        let buffer = write_bytes(state.k0, state.k1, [state.k2, state.k3], data.as_bytes());
+        // `Hash` for `str` writes `0xff` after the bytes.
+        finish(update(buffer, 0xff), state.k1)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::v1::*;
+    use core::hash::{BuildHasher, Hash, Hasher};
+
+    fn hash_bytes(state: &StableRandomState, data: &[u8]) -> u64 {
+        let mut hasher = state.build_hasher();
+        hasher.write(data);
+        hasher.finish()
+    }
+
+    /// These values must never change. If this test fails the change must be reverted and made in a new version.
+    #[test]
+    fn test_v1_golden_bytes() {
+        let state = StableRandomState::with_seeds(1, 2, 3, 4);
+        let input: Vec<u8> = (0..=255).collect();
+        let expected: [(usize, u64); 12] = [
+            (0, 0x2ae2_b2f2_b7e6_00f2),
+            (1, 0xae90_bb9f_cf31_bb36),
+            (3, 0xc33c_98f3_647e_5228),
+            (4, 0x1cc7_fe84_f345_c250),
+            (7, 0xce2b_4712_e635_c249),
+            (8, 0xe113_cd87_fdf4_f80f),
+            (9, 0xee43_ea7e_a772_2a97),
+            (16, 0x0234_3352_1c92_51a2),
+            (17, 0xb627_cff0_9297_148d),
+            (32, 0xe60f_9309_e00b_d5ee),
+            (100, 0xa775_003b_16fd_790a),
+            (256, 0x5183_e2af_ccc5_134a),
+        ];
+        for (len, hash) in expected.iter() {
+            assert_eq!(*hash, hash_bytes(&state, &input[..*len]), "Length {}", len);
+        }
+    }
+
+    /// These values must never change. If this test fails the change must be reverted and made in a new version.
+    #[test]
+    fn test_v1_golden_primitives() {
+        let state = StableRandomState::with_seeds(1, 2, 3, 4);
+        assert_eq!(0x51f2_6d19_6d31_9c9b, state.hash_one(0_u8));
+        assert_eq!(0x72b1_955c_2954_8bcc, state.hash_one(0x1234_u16));
+        assert_eq!(0x0097_3bcc_5275_18fe, state.hash_one(0x1234_5678_u32));
+        assert_eq!(0x0ad6_677f_1da8_7816, state.hash_one(0x1234_5678_9abc_def0_u64));
+        assert_eq!(0xeb64_230e_f7ef_13c5, state.hash_one(0x1234_5678_9abc_def0_0fed_cba9_8765_4321_u128));
+        assert_eq!(0x9b34_70a8_0722_ec79, state.hash_one(12345_usize));
+        assert_eq!(0x6746_831e_1b30_68ff, state.hash_one(-1_i64));
+        assert_eq!(0xfde3_dd43_eb0f_df4b, state.hash_one("Hello, world!"));
+        assert_eq!(0x1807_28cb_5208_3075, state.hash_one((1_u32, "a", [2_u64, 3])));
+    }
+
+    /// These values must never change. If this test fails the change must be reverted and made in a new version.
+    #[test]
+    fn test_v1_golden_keys() {
+        assert_eq!(0x27da_a1f9_f056_cdb3, StableRandomState::default().hash_one("test"));
+        assert_eq!(0x9789_9a89_32f8_b26e, StableRandomState::with_seeds(0, 0, 0, 0).hash_one("test"));
+        assert_eq!(0x9969_562c_c2a4_c694, StableRandomState::with_seeds(u64::MAX, 0, 0, 0).hash_one("test"));
+        assert_eq!(0xf8db_4476_eca2_924c, StableRandomState::with_seeds(0, 0, 0, u64::MAX).hash_one("test"));
+    }
+
+    #[test]
+    fn test_usize_is_hashed_as_u64() {
+        let state = StableRandomState::with_seeds(5, 6, 7, 8);
+        assert_eq!(state.hash_one(1234_usize), state.hash_one(1234_u64));
+        assert_eq!(state.hash_one(-5_isize), state.hash_one(-5_i64));
+    }
+
+    #[test]
+    fn test_keys_change_output() {
+        let a = StableRandomState::with_seeds(1, 2, 3, 4).hash_one("test");
+        let b = StableRandomState::with_seeds(1, 2, 3, 5).hash_one("test");
+        let c = StableRandomState::with_seeds(2, 2, 3, 4).hash_one("test");
+        assert_ne!(a, b);
+        assert_ne!(a, c);
+        assert_ne!(b, c);
+    }
+
+    #[test]
+    fn test_values_change_output() {
+        let state = StableRandomState::with_seeds(1, 2, 3, 4);
+        let mut hasher = state.build_hasher();
+        "test".hash(&mut hasher);
+        let first = hasher.finish();
+        "test".hash(&mut hasher);
+        assert_ne!(first, hasher.finish());
+        assert_ne!(state.hash_one([0_u8; 20]), state.hash_one([1_u8; 20]));
+    }
+
+    #[test]
+    fn test_const_matches_hasher() {
+        const SEED: [u64; 4] = [1, 2, 3, 4];
+        const HELLO: u64 = hash_str_const(SEED, "Hello, world!");
+        const EMPTY: u64 = hash_bytes_const(SEED, b"");
+        let state = StableRandomState::with_seeds(1, 2, 3, 4);
+        assert_eq!(HELLO, state.hash_one("Hello, world!"));
+        assert_eq!(EMPTY, state.hash_one(&b""[..]));
+
+        let input: Vec<u8> = (0..=255).collect();
+        for seed in [[1, 2, 3, 4], [u64::MAX, 0, 0, u64::MAX]] {
+            let state = StableRandomState::with_seeds(seed[0], seed[1], seed[2], seed[3]);
+            for len in 0..=input.len() {
+                let data = &input[..len];
+                assert_eq!(hash_bytes_const(seed, data), state.hash_one(data), "Length {}", len);
+            }
+            for s in ["", "a", "abcd", "a longer string of text", "ü"] {
+                assert_eq!(hash_str_const(seed, s), state.hash_one(s));
+            }
+        }
+    }
+}
@@ -0,0 +1,265 @@
+use crate::{AHasher, RandomState};
+use core::fmt;
+use core::hash::{BuildHasher, Hasher};
+
+/// The number of bytes passed to the underlying [AHasher] at a time.
+const BLOCK_SIZE: usize = 256;
+
+/// A hasher for byte streams whose result does not depend on how the stream is split up.
+///
+/// [AHasher::write] mixes the length of each call into the hash, so `write(a); write(b)` produces a different result
+/// from `write(a ++ b)`. An `AHashStream` instead buffers the input and hashes it in fixed size blocks, so that any
+/// sequence of writes which concatenate to the same bytes produces the same hash. This allows large files or network
+/// payloads to be hashed incrementally in whatever sized pieces happen to be available.
+///
+/// The result is not the same as hashing all of the data with a single call to [AHasher::write].
+///
+/// If the `std` feature is enabled this also implements [std::io::Write], and [RandomState::hash_reader] can be used
+/// to hash everything from a [std::io::Read].
+///
+/// It also implements [fmt::Write], so a value can be formatted directly into the stream with `write!` rather than
+/// into a `String` which is then hashed. (See [RandomState::hash_display])
+///
+/// # Example
+/// ```
+/// use ahash::{AHashStream, RandomState};
+/// use std::hash::Hasher;
+///
+/// let state = RandomState::with_seed(42);
+/// let mut a = AHashStream::new(&state);
+/// a.write(b"Hello, ");
+/// a.write(b"world!");
+/// let mut b = AHashStream::new(&state);
+/// b.write(b"Hello, world!");
+/// assert_eq!(a.finish(), b.finish());
+/// ```
+#[derive(Clone)]
+pub struct AHashStream {
+    hasher: AHasher,
+    buffer: [u8; BLOCK_SIZE],
+    buffered: usize,
+    length: u64,
+}
+
+impl AHashStream {
+    /// Creates a stream which hashes using the keys of the provided [RandomState].
+    #[inline]
+    pub fn new(state: &RandomState) -> AHashStream {
+        AHashStream {
+            hasher: state.build_hasher(),
+            buffer: [0; BLOCK_SIZE],
+            buffered: 0,
+            length: 0,
+        }
+    }
+
+    /// The total number of bytes written so far.
+    #[inline]
+    pub fn len(&self) -> u64 {
+        self.length
+    }
+
+    /// Returns true if no bytes have been written.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.length == 0
+    }
+
+    /// Computes the hash of the bytes written so far, including those which do not yet fill a block.
+    #[inline]
+    fn finish_hasher(&self) -> AHasher {
+        let mut hasher = self.hasher.clone();
+        // The tail is written with its length, and the total length makes streams which end in a partial block
+        // distinct from those which are padded out to a full one.
+        hasher.write(&self.buffer[..self.buffered]);
+        hasher.write_u64(self.length);
+        hasher
+    }
+
+    /// Computes a 128 bit hash of the bytes written so far. (See [AHasher::finish_u128])
+    #[inline]
+    pub fn finish_u128(&self) -> u128 {
+        self.finish_hasher().finish_u128()
+    }
+}
+
+/// Creates a stream with fixed keys. The same as using [AHasher::default].
+impl Default for AHashStream {
+    #[inline]
+    fn default() -> AHashStream {
+        AHashStream::new(&RandomState::with_fixed_keys())
+    }
+}
+
+impl fmt::Debug for AHashStream {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AHashStream").field("length", &self.length).finish()
+    }
+}
+
+/// All of the `write_*` methods are treated as writing the native endian bytes of the value into the stream.
+impl Hasher for AHashStream {
+    #[inline]
+    fn write(&mut self, mut bytes: &[u8]) {
+        self.length = self.length.wrapping_add(bytes.len() as u64);
+        if self.buffered > 0 {
+            let take = bytes.len().min(BLOCK_SIZE - self.buffered);
+            self.buffer[self.buffered..self.buffered + take].copy_from_slice(&bytes[..take]);
+            self.buffered += take;
+            bytes = &bytes[take..];
+            if self.buffered < BLOCK_SIZE {
+                return;
+            }
+            self.hasher.write(&self.buffer);
+            self.buffered = 0;
+        }
+        let mut blocks = bytes.chunks_exact(BLOCK_SIZE);
+        for block in &mut blocks {
+            self.hasher.write(block);
+        }
+        let remainder = blocks.remainder();
+        self.buffer[..remainder.len()].copy_from_slice(remainder);
+        self.buffered = remainder.len();
+    }
+
+    #[inline]
+    fn finish(&self) -> u64 {
+        self.finish_hasher().finish()
+    }
+}
+
+/// Formatted text is hashed as its UTF-8 bytes, so the result is the same as writing the fully formatted string.
+impl fmt::Write for AHashStream {
+    #[inline]
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        Hasher::write(self, s.as_bytes());
+        Ok(())
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::io::Write for AHashStream {
+    #[inline]
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        Hasher::write(self, buf);
+        Ok(buf.len())
+    }
+
+    #[inline]
+    fn write_all(&mut self, buf: &[u8]) -> std::io::Result<()> {
+        Hasher::write(self, buf);
+        Ok(())
+    }
+
+    #[inline]
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn stream_hash(state: &RandomState, chunks: &[&[u8]]) -> u64 {
+        let mut stream = AHashStream::new(state);
+        for chunk in chunks {
+            stream.write(chunk);
+        }
+        stream.finish()
+    }
+
+    #[test]
+    fn test_chunk_invariant() {
+        let state = RandomState::with_seeds(1, 2, 3, 4);
+        let data: Vec<u8> = (0..2000_u32).map(|i| (i * 7 + i / 13) as u8).collect();
+        for len in [0, 1, 15, 255, 256, 257, 511, 512, 1000, 2000] {
+            let data = &data[..len];
+            let expected = stream_hash(&state, &[data]);
+            for split in 0..=len.min(300) {
+                let (a, b) = data.split_at(split);
+                assert_eq!(expected, stream_hash(&state, &[a, b]), "len {} split {}", len, split);
+            }
+            for chunk_size in [1, 3, 64, 100, 256, 333] {
+                let chunks: Vec<&[u8]> = data.chunks(chunk_size).collect();
+                assert_eq!(expected, stream_hash(&state, &chunks), "len {} chunk {}", len, chunk_size);
+            }
+            let with_empty: [&[u8]; 3] = [&[], data, &[]];
+            assert_eq!(expected, stream_hash(&state, &with_empty));
+        }
+    }
+
+    #[test]
+    fn test_distinguishes_inputs() {
+        let state = RandomState::with_seeds(1, 2, 3, 4);
+        let zeros = [0_u8; 600];
+        let mut hashes: Vec<u64> = (0..=zeros.len()).map(|len| stream_hash(&state, &[&zeros[..len]])).collect();
+        hashes.sort_unstable();
+        hashes.dedup();
+        assert_eq!(hashes.len(), zeros.len() + 1);
+        assert_ne!(stream_hash(&state, &[b"abc"]), stream_hash(&state, &[b"abd"]));
+        assert_ne!(stream_hash(&state, &[b"abc"]), stream_hash(&RandomState::with_seeds(1, 2, 3, 5), &[b"abc"]));
+    }
+
+    #[test]
+    fn test_finish_does_not_consume() {
+        let state = RandomState::with_seeds(1, 2, 3, 4);
+        let mut stream = AHashStream::new(&state);
+        stream.write(&[1; 300]);
+        let first = stream.finish();
+        assert_eq!(first, stream.finish());
+        stream.write(&[2; 10]);
+        assert_ne!(first, stream.finish());
+        assert_eq!(stream.len(), 310);
+        assert_eq!(stream.finish(), stream_hash(&state, &[&[1; 300], &[2; 10]]));
+    }
+
+    struct Pieces(&'static [&'static str]);
+
+    /// Writes its output in several pieces, the way derived and nested `Display` implementations do.
+    impl fmt::Display for Pieces {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            for piece in self.0 {
+                f.write_str(piece)?;
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_fmt_write() {
+        use core::fmt::Write;
+
+        let state = RandomState::with_seeds(1, 2, 3, 4);
+        let mut stream = AHashStream::new(&state);
+        write!(stream, "{}-{:?}-{:>5}", 12, "ab", 'c').unwrap();
+        assert_eq!(stream.finish(), stream_hash(&state, &[b"12-\"ab\"-    c"]));
+
+        let pieces = Pieces(&["SELECT ", "*", " FROM ", "table"]);
+        assert_eq!(state.hash_display(&pieces), stream_hash(&state, &[b"SELECT * FROM table"]));
+        assert_eq!(state.hash_display(&pieces), state.hash_display(&"SELECT * FROM table"));
+        assert_ne!(state.hash_display(&pieces), state.hash_display(&"SELECT * FROM tables"));
+        assert_eq!(state.hash_display(&Pieces(&[])), stream_hash(&state, &[]));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_io_write_and_reader() {
+        use std::io::Write;
+
+        let state = RandomState::with_seeds(1, 2, 3, 4);
+        let data: Vec<u8> = (0..10_000_u32).map(|i| (i % 251) as u8).collect();
+        let mut stream = AHashStream::new(&state);
+        for chunk in data.chunks(1234) {
+            stream.write_all(chunk).unwrap();
+        }
+        stream.flush().unwrap();
+        assert_eq!(stream.finish(), stream_hash(&state, &[&data]));
+        assert_eq!(stream.finish(), state.hash_reader(&data[..]).unwrap());
+        assert_eq!(stream.finish_u128(), {
+            let mut other = AHashStream::new(&state);
+            std::io::copy(&mut &data[..], &mut other).unwrap();
+            other.finish_u128()
+        });
+    }
+}
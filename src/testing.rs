@@ -0,0 +1,489 @@
+//! Support for testing code which uses aHash. (Requires the `testing` feature)
+//!
+//! # Deterministic seeds
+//! Normally every [RandomState::new()] has different keys, and they change each time the program is run. This makes
+//! the iteration order of an [AHashMap](crate::AHashMap) differ between runs, which breaks tests that compare against
+//! a snapshot of the output.
+//!
+//! If the `testing` feature is enabled and the environment variable `AHASH_DETERMINISTIC_SEED` is set to an unsigned
+//! integer when the first [RandomState] is created, the whole process uses keys derived from that number instead:
+//! * The process wide seeds (used by [RandomState::with_seed] and [Default] for [AHasher](crate::AHasher)) are derived
+//!   from it instead of being generated at runtime or compile time.
+//! * Every [RandomState::new()] returns the same keys, regardless of how many states were created before or on which
+//!   thread. This takes precedence over a source installed with [set_random_source](crate::random_state::set_random_source).
+//!
+//! Rerunning the program with the same value then gives the same hashes and the same map iteration order. (As long
+//! as it runs on the same CPU and version of aHash, see [stable](crate::stable) otherwise)
+//!
+//! ```text
+//! AHASH_DETERMINISTIC_SEED=42 cargo test
+//! ```
+//!
+//! This removes all protection against HashDoS, so it should only be set for tests. The variable is read once, so
+//! setting or changing it after the first `RandomState` has been created has no effect. If it is set to anything other
+//! than an unsigned integer, creating the first `RandomState` panics rather than silently using random seeds.
+//!
+//! # Checking `Hash` implementations
+//! A hand written [Hash] implementation which does not agree with `Eq`, or which writes the same data for different
+//! values, does not cause any errors. Instead maps silently fail to find entries or become slow. [check_hash_impl]
+//! records the `write_*` calls made by the implementation for a set of sample values and reports such problems:
+//!
+//! ```
+//! use ahash::testing::check_hash_impl;
+//! use std::hash::{Hash, Hasher};
+//!
+//! #[derive(PartialEq, Eq)]
+//! struct Name(String, String);
+//!
+//! impl Hash for Name {
+//!     fn hash<H: Hasher>(&self, state: &mut H) {
+//!         // Wrong: ("ab", "c") writes the same bytes as ("a", "bc")
+//!         state.write(self.0.as_bytes());
+//!         state.write(self.1.as_bytes());
+//!     }
+//! }
+//!
+//! let samples = [Name("ab".into(), "c".into()), Name("a".into(), "bc".into())];
+//! assert!(check_hash_impl(&samples).is_err());
+//! ```
+use crate::random_state::RandomSource;
+use crate::{AHasher, RandomState};
+use core::fmt;
+use core::hash::{Hash, Hasher};
+use once_cell::race::OnceBox;
+
+/// The environment variable that enables deterministic seeds.
+pub const DETERMINISTIC_SEED_VAR: &str = "AHASH_DETERMINISTIC_SEED";
+
+pub(crate) struct Deterministic {
+    seed: u64,
+    fixed: [[u64; 4]; 2],
+}
+
+impl Deterministic {
+    fn new(seed: u64) -> Deterministic {
+        let state = RandomState::with_seeds(seed, 0, 0, 0);
+        let mut fixed = [[0; 4]; 2];
+        for (i, key) in fixed.iter_mut().flatten().enumerate() {
+            *key = state.hash_one(i as u64);
+        }
+        Deterministic { seed, fixed }
+    }
+
+    #[inline]
+    pub(crate) fn fixed_seeds(&self) -> &[[u64; 4]; 2] {
+        &self.fixed
+    }
+}
+
+impl RandomSource for Deterministic {
+    #[inline]
+    fn gen_hasher_seed(&self) -> usize {
+        self.seed as usize
+    }
+}
+
+static DETERMINISTIC: OnceBox<Option<Deterministic>> = OnceBox::new();
+
+/// Returns the deterministic seeds if `AHASH_DETERMINISTIC_SEED` is set.
+#[inline]
+pub(crate) fn deterministic() -> Option<&'static Deterministic> {
+    DETERMINISTIC
+        .get_or_init(|| {
+            let seed = std::env::var_os(DETERMINISTIC_SEED_VAR).map(|value| {
+                value
+                    .to_str()
+                    .and_then(|value| value.trim().parse().ok())
+                    .unwrap_or_else(|| {
+                        panic!("{} must be set to an unsigned integer, not {:?}.", DETERMINISTIC_SEED_VAR, value)
+                    })
+            });
+            Box::new(seed.map(Deterministic::new))
+        })
+        .as_ref()
+}
+
+/// The same as [deterministic], but returning `None` instead of reading the variable if that has not been done yet.
+#[inline]
+pub(crate) fn deterministic_if_read() -> Option<Option<&'static Deterministic>> {
+    DETERMINISTIC.get().map(Option::as_ref)
+}
+
+/// Returns the value of `AHASH_DETERMINISTIC_SEED` if deterministic seeds are in use by this process.
+///
+/// This can be used by tests which depend on a fixed iteration order to check that the variable was set.
+///
+/// # Panics
+/// If the variable is set but is not an unsigned integer. (Creating a [RandomState] panics in the same way)
+pub fn deterministic_seed() -> Option<u64> {
+    deterministic().map(|d| d.seed)
+}
+
+/// A call made by a [Hash] implementation to the [Hasher] it was given. (See [hash_calls])
+///
+/// The signed `write_i*` methods are recorded as their unsigned equivalents, as that is what they forward to.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum HashCall {
+    /// [Hasher::write]
+    Bytes(Vec<u8>),
+    /// [Hasher::write_u8]
+    U8(u8),
+    /// [Hasher::write_u16]
+    U16(u16),
+    /// [Hasher::write_u32]
+    U32(u32),
+    /// [Hasher::write_u64]
+    U64(u64),
+    /// [Hasher::write_u128]
+    U128(u128),
+    /// [Hasher::write_usize]
+    Usize(usize),
+}
+
+impl HashCall {
+    /// Appends the bytes a byte oriented hasher would see for this call.
+    fn append_bytes(&self, bytes: &mut Vec<u8>) {
+        match self {
+            HashCall::Bytes(b) => bytes.extend_from_slice(b),
+            HashCall::U8(i) => bytes.extend_from_slice(&i.to_ne_bytes()),
+            HashCall::U16(i) => bytes.extend_from_slice(&i.to_ne_bytes()),
+            HashCall::U32(i) => bytes.extend_from_slice(&i.to_ne_bytes()),
+            HashCall::U64(i) => bytes.extend_from_slice(&i.to_ne_bytes()),
+            HashCall::U128(i) => bytes.extend_from_slice(&i.to_ne_bytes()),
+            HashCall::Usize(i) => bytes.extend_from_slice(&i.to_ne_bytes()),
+        }
+    }
+}
+
+/// A [Hasher] which records the calls made to it.
+#[derive(Default)]
+struct RecordingHasher {
+    calls: Vec<HashCall>,
+}
+
+impl Hasher for RecordingHasher {
+    fn finish(&self) -> u64 {
+        let mut hasher = AHasher::default();
+        self.calls.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        self.calls.push(HashCall::Bytes(bytes.to_vec()));
+    }
+
+    fn write_u8(&mut self, i: u8) {
+        self.calls.push(HashCall::U8(i));
+    }
+
+    fn write_u16(&mut self, i: u16) {
+        self.calls.push(HashCall::U16(i));
+    }
+
+    fn write_u32(&mut self, i: u32) {
+        self.calls.push(HashCall::U32(i));
+    }
+
+    fn write_u64(&mut self, i: u64) {
+        self.calls.push(HashCall::U64(i));
+    }
+
+    fn write_u128(&mut self, i: u128) {
+        self.calls.push(HashCall::U128(i));
+    }
+
+    fn write_usize(&mut self, i: usize) {
+        self.calls.push(HashCall::Usize(i));
+    }
+}
+
+/// Returns the calls that the [Hash] implementation of `value` makes to a [Hasher].
+pub fn hash_calls<T: Hash + ?Sized>(value: &T) -> Vec<HashCall> {
+    let mut hasher = RecordingHasher::default();
+    value.hash(&mut hasher);
+    hasher.calls
+}
+
+/// A problem with a [Hash] implementation found by [check_hash_impl]. The fields are indexes into the samples.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum HashImplError {
+    /// The samples are equal, but they do not make the same calls to the hasher, so they will generally hash
+    /// differently. (For example a field is hashed but not compared)
+    EqualButHashedDifferently {
+        /// The index of the first sample.
+        first: usize,
+        /// The index of the second sample.
+        second: usize,
+    },
+    /// The samples are not equal, but they make exactly the same calls to the hasher, so they always collide.
+    DifferentButSameCalls {
+        /// The index of the first sample.
+        first: usize,
+        /// The index of the second sample.
+        second: usize,
+    },
+    /// The samples are not equal and make different calls, but the calls write the same sequence of bytes, so they
+    /// collide with hashers which do not distinguish where one write ends and the next begins. (For example
+    /// strings written without a length or terminator)
+    DifferentButSameBytes {
+        /// The index of the first sample.
+        first: usize,
+        /// The index of the second sample.
+        second: usize,
+    },
+}
+
+impl fmt::Display for HashImplError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HashImplError::EqualButHashedDifferently { first, second } => write!(
+                f,
+                "samples {} and {} are equal but make different calls to the hasher",
+                first, second
+            ),
+            HashImplError::DifferentButSameCalls { first, second } => write!(
+                f,
+                "samples {} and {} are not equal but make the same calls to the hasher",
+                first, second
+            ),
+            HashImplError::DifferentButSameBytes { first, second } => write!(
+                f,
+                "samples {} and {} are not equal but write the same bytes to the hasher",
+                first, second
+            ),
+        }
+    }
+}
+
+impl std::error::Error for HashImplError {}
+
+/// Checks that the [Hash] implementation of `T` is consistent with its `Eq` implementation and distinguishes the
+/// provided samples.
+///
+/// Every pair of samples is compared, and problems are returned for pairs which:
+/// * are equal but make different calls to the hasher, or
+/// * are not equal but make the same calls, or write the same bytes. (Only the first of these is reported for a
+///   pair)
+///
+/// The samples should include values which are equal but constructed differently, and values which differ in only
+/// one field or in where the boundary between fields falls. This compares every pair, so it takes time quadratic in
+/// the number of samples.
+pub fn check_hash_impl<T: Hash + Eq>(samples: &[T]) -> Result<(), Vec<HashImplError>> {
+    let recorded: Vec<(Vec<HashCall>, Vec<u8>)> = samples
+        .iter()
+        .map(|sample| {
+            let calls = hash_calls(sample);
+            let mut bytes = Vec::new();
+            for call in &calls {
+                call.append_bytes(&mut bytes);
+            }
+            (calls, bytes)
+        })
+        .collect();
+    let mut errors = Vec::new();
+    for first in 0..samples.len() {
+        for second in first + 1..samples.len() {
+            let (first_calls, first_bytes) = &recorded[first];
+            let (second_calls, second_bytes) = &recorded[second];
+            if samples[first] == samples[second] {
+                if first_calls != second_calls {
+                    errors.push(HashImplError::EqualButHashedDifferently { first, second });
+                }
+            } else if first_calls == second_calls {
+                errors.push(HashImplError::DifferentButSameCalls { first, second });
+            } else if first_bytes == second_bytes {
+                errors.push(HashImplError::DifferentButSameBytes { first, second });
+            }
+        }
+    }
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::AHashMap;
+    use std::process::{Command, Output};
+
+    /// The variable holding the output [check_order] expects, or `none` if deterministic seeds should not be in use.
+    const EXPECTED_VAR: &str = "AHASH_TEST_EXPECTED_ORDER";
+    /// The exit status of [check_order] if the output does not match.
+    const MISMATCH: i32 = 3;
+
+    fn order(state: RandomState) -> String {
+        let mut map = AHashMap::from(std::collections::HashMap::with_hasher(state.clone()));
+        for i in 0..100 {
+            map.insert(i, i);
+        }
+        let order: Vec<String> = map.keys().map(|k| k.to_string()).collect();
+        format!("{} {}", order.join(","), state.hash_one("x"))
+    }
+
+    /// Compares the iteration order of a map with [EXPECTED_VAR]. Run in a child process by the tests below.
+    #[test]
+    #[ignore]
+    fn check_order() {
+        // When run on its own, such as by `cargo test -- --ignored`, there is nothing to compare against.
+        let expected = match std::env::var(EXPECTED_VAR) {
+            Ok(expected) => expected,
+            Err(_) => return,
+        };
+        let actual = match deterministic_seed() {
+            Some(_) => order(RandomState::new()),
+            None => "none".into(),
+        };
+        if actual != expected {
+            std::process::exit(MISMATCH);
+        }
+    }
+
+    /// The output of [check_order] when run with `seed`.
+    fn expected_order(seed: u64) -> String {
+        let d = Deterministic::new(seed);
+        order(RandomState::from_keys(&d.fixed_seeds()[0], &d.fixed_seeds()[1], d.gen_hasher_seed()))
+    }
+
+    /// Runs [check_order] in a child process.
+    fn run_check_order(seed: &str, expected: &str) -> Output {
+        Command::new(std::env::current_exe().unwrap())
+            .args(["--ignored", "--exact", "testing::test::check_order", "--test-threads=1"])
+            .env(DETERMINISTIC_SEED_VAR, seed)
+            .env(EXPECTED_VAR, expected)
+            .output()
+            .unwrap()
+    }
+
+    /// Runs [check_order] in a child process and returns its exit status.
+    fn run_with_seed(seed: &str, expected: &str) -> Option<i32> {
+        run_check_order(seed, expected).status.code()
+    }
+
+    #[test]
+    fn test_reruns_are_identical() {
+        let expected = expected_order(42);
+        assert_eq!(run_with_seed("42", &expected), Some(0));
+        assert_eq!(run_with_seed("42", &expected), Some(0));
+        assert_eq!(run_with_seed(" 42\n", &expected), Some(0));
+        assert_eq!(run_with_seed("43", &expected), Some(MISMATCH));
+        assert_eq!(run_with_seed("43", &expected_order(43)), Some(0));
+    }
+
+    #[test]
+    fn test_malformed_seed_panics() {
+        for seed in ["forty-two", "-1", ""] {
+            let output = run_check_order(seed, "none");
+            assert!(!output.status.success());
+            assert_ne!(output.status.code(), Some(MISMATCH));
+            let message = format!("{} must be set to an unsigned integer, not {:?}.", DETERMINISTIC_SEED_VAR, seed);
+            assert!(String::from_utf8_lossy(&output.stdout).contains(&message));
+        }
+    }
+
+    #[test]
+    fn test_derived_seeds() {
+        let d = Deterministic::new(42);
+        assert_eq!(d.fixed_seeds(), Deterministic::new(42).fixed_seeds());
+        assert_ne!(d.fixed_seeds(), Deterministic::new(43).fixed_seeds());
+        assert_ne!(d.fixed_seeds()[0], d.fixed_seeds()[1]);
+        assert_eq!(d.gen_hasher_seed(), d.gen_hasher_seed());
+    }
+
+    /// Hashes `id` but only compares `name`.
+    #[derive(Debug)]
+    struct FieldNotCompared {
+        name: &'static str,
+        id: u32,
+    }
+
+    impl PartialEq for FieldNotCompared {
+        fn eq(&self, other: &Self) -> bool {
+            self.name == other.name
+        }
+    }
+
+    impl Eq for FieldNotCompared {}
+
+    impl Hash for FieldNotCompared {
+        fn hash<H: Hasher>(&self, state: &mut H) {
+            self.name.hash(state);
+            self.id.hash(state);
+        }
+    }
+
+    /// Writes both strings without anything between them.
+    #[derive(Debug, PartialEq, Eq)]
+    struct Concatenated(&'static str, &'static str);
+
+    impl Hash for Concatenated {
+        fn hash<H: Hasher>(&self, state: &mut H) {
+            state.write(self.0.as_bytes());
+            state.write(self.1.as_bytes());
+        }
+    }
+
+    /// Ignores the second field.
+    #[derive(Debug, PartialEq, Eq)]
+    struct Partial(u8, u8);
+
+    impl Hash for Partial {
+        fn hash<H: Hasher>(&self, state: &mut H) {
+            self.0.hash(state);
+        }
+    }
+
+    #[test]
+    fn test_hash_calls() {
+        assert_eq!(hash_calls(&1_i16), vec![HashCall::U16(1)]);
+        assert_eq!(
+            hash_calls("ab"),
+            vec![HashCall::Bytes(b"ab".to_vec()), HashCall::U8(0xff)]
+        );
+        assert_eq!(hash_calls(&(1_u8, 2_u128)), vec![HashCall::U8(1), HashCall::U128(2)]);
+    }
+
+    #[test]
+    fn test_correct_impls() {
+        assert_eq!(check_hash_impl(&[("ab", "c"), ("a", "bc"), ("", "abc"), ("abc", ""), ("ab", "c")]), Ok(()));
+        assert_eq!(check_hash_impl(&[vec![vec![1_u8], vec![]], vec![vec![], vec![1]], vec![vec![1]]]), Ok(()));
+        assert_eq!(check_hash_impl(&[Some(0_u32), None, Some(1)]), Ok(()));
+    }
+
+    #[test]
+    fn test_equal_but_hashed_differently() {
+        let samples = [
+            FieldNotCompared { name: "a", id: 1 },
+            FieldNotCompared { name: "a", id: 2 },
+            FieldNotCompared { name: "b", id: 1 },
+        ];
+        let errors = check_hash_impl(&samples).unwrap_err();
+        assert_eq!(errors, vec![HashImplError::EqualButHashedDifferently { first: 0, second: 1 }]);
+        assert_eq!(
+            errors[0].to_string(),
+            "samples 0 and 1 are equal but make different calls to the hasher"
+        );
+    }
+
+    #[test]
+    fn test_different_but_same_calls() {
+        let samples = [Partial(1, 1), Partial(1, 2), Partial(2, 1)];
+        assert_eq!(
+            check_hash_impl(&samples),
+            Err(vec![HashImplError::DifferentButSameCalls { first: 0, second: 1 }])
+        );
+    }
+
+    #[test]
+    fn test_different_but_same_bytes() {
+        let samples = [Concatenated("ab", "c"), Concatenated("a", "bc"), Concatenated("ab", "d")];
+        assert_eq!(
+            check_hash_impl(&samples),
+            Err(vec![HashImplError::DifferentButSameBytes { first: 0, second: 1 }])
+        );
+    }
+}
@@ -9,7 +9,9 @@ use std::collections::hash_map::DefaultHasher;
 use std::hash::{BuildHasherDefault, Hash, Hasher};
 
 // Needs to be in sync with `src/lib.rs`
-const AHASH_IMPL: &str = if cfg!(any(
+const AHASH_IMPL: &str = if cfg!(feature = "force-fallback") {
+    "fallbackhash"
+} else if cfg!(any(
     all(
         any(target_arch = "x86", target_arch = "x86_64"),
         target_feature = "aes",
@@ -112,6 +114,108 @@ fn bench_sip(c: &mut Criterion) {
     bench_inputs!(group, siphash);
 }
 
+fn bench_hash_many(c: &mut Criterion) {
+    let build_hasher = RandomState::with_seeds(1, 2, 3, 4);
+    let mut rng = rand::thread_rng();
+    let u32s: Vec<u32> = (0..1024).map(|_| rng.gen()).collect();
+    let u64s: Vec<u64> = (0..1024).map(|_| rng.gen()).collect();
+    let u128s: Vec<u128> = (0..1024).map(|_| rng.gen()).collect();
+    let strings: Vec<String> = (0..1024).map(|i| format!("key{}", i)).collect();
+    let mut hashes = vec![0; 1024];
+
+    let mut group = c.benchmark_group("hash_many");
+    group.bench_function("u32-hash_one-loop", |b| {
+        b.iter(|| {
+            for (value, hash) in u32s.iter().zip(hashes.iter_mut()) {
+                *hash = build_hasher.hash_one(*value);
+            }
+            black_box(&hashes);
+        })
+    });
+    group.bench_function("u32-hash_many", |b| {
+        b.iter(|| {
+            build_hasher.hash_many(&u32s, &mut hashes);
+            black_box(&hashes);
+        })
+    });
+    group.bench_function("u64-hash_one-loop", |b| {
+        b.iter(|| {
+            for (value, hash) in u64s.iter().zip(hashes.iter_mut()) {
+                *hash = build_hasher.hash_one(*value);
+            }
+            black_box(&hashes);
+        })
+    });
+    group.bench_function("u64-hash_u64-loop", |b| {
+        b.iter(|| {
+            for (value, hash) in u64s.iter().zip(hashes.iter_mut()) {
+                *hash = build_hasher.hash_u64(*value);
+            }
+            black_box(&hashes);
+        })
+    });
+    group.bench_function("u64-hash_many", |b| {
+        b.iter(|| {
+            build_hasher.hash_many(&u64s, &mut hashes);
+            black_box(&hashes);
+        })
+    });
+    group.bench_function("u128-hash_one-loop", |b| {
+        b.iter(|| {
+            for (value, hash) in u128s.iter().zip(hashes.iter_mut()) {
+                *hash = build_hasher.hash_one(*value);
+            }
+            black_box(&hashes);
+        })
+    });
+    group.bench_function("u128-hash_many", |b| {
+        b.iter(|| {
+            build_hasher.hash_many(&u128s, &mut hashes);
+            black_box(&hashes);
+        })
+    });
+    group.bench_function("strings-hash_one-loop", |b| {
+        b.iter(|| {
+            for (value, hash) in strings.iter().zip(hashes.iter_mut()) {
+                *hash = build_hasher.hash_one(value);
+            }
+            black_box(&hashes);
+        })
+    });
+    group.bench_function("strings-hash_str-loop", |b| {
+        b.iter(|| {
+            for (value, hash) in strings.iter().zip(hashes.iter_mut()) {
+                *hash = build_hasher.hash_str(value);
+            }
+            black_box(&hashes);
+        })
+    });
+    group.bench_function("strings-hash_many", |b| {
+        b.iter(|| {
+            build_hasher.hash_many(&strings, &mut hashes);
+            black_box(&hashes);
+        })
+    });
+}
+
+fn bench_key(c: &mut Criterion) {
+    let build_hasher = RandomState::with_seeds(1, 2, 3, 4);
+    let mut group = c.benchmark_group("key");
+    group.bench_function("u64-hash_one", |b| b.iter(|| build_hasher.hash_one(black_box(1234_u64))));
+    group.bench_function("u64-hash_u64", |b| b.iter(|| build_hasher.hash_u64(black_box(1234_u64))));
+    group.bench_function("str-hash_one", |b| b.iter(|| build_hasher.hash_one(black_box("short"))));
+    group.bench_function("str-hash_str", |b| b.iter(|| build_hasher.hash_str(black_box("short"))));
+}
+
+fn bench_random_state(c: &mut Criterion) {
+    #[cfg(feature = "std")]
+    {
+        // `ThreadLocalRandomSource` is benchmarked in `random_state_bench.rs`, as the source can only be set once.
+        let mut group = c.benchmark_group("random_state");
+        group.bench_function("new-default", |b| b.iter(RandomState::new));
+    }
+}
+
 fn bench_map(c: &mut Criterion) {
     #[cfg(feature = "std")]
     {
@@ -195,5 +299,8 @@ criterion_group!(
     bench_fnv,
     bench_sea,
     bench_sip,
+    bench_hash_many,
+    bench_key,
+    bench_random_state,
     bench_map
 );
\ No newline at end of file
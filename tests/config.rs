@@ -0,0 +1,14 @@
+#![cfg(feature = "runtime-rng")]
+
+use ahash::{RandomState, SeedSource};
+
+// This is a single test so that no other thread creates a state before `config()` is first called.
+#[test]
+fn test_config_does_not_obtain_seeds() {
+    let config = ahash::config();
+    assert_eq!(config.seed_source, SeedSource::NotSeeded);
+    assert!(!config.reseeded_after_fork);
+
+    let _ = RandomState::new();
+    assert_ne!(ahash::config().seed_source, SeedSource::NotSeeded);
+}
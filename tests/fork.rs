@@ -0,0 +1,100 @@
+#![cfg(all(unix, feature = "runtime-rng"))]
+
+use ahash::{AHasher, RandomState};
+use std::collections::HashMap;
+use std::hash::BuildHasherDefault;
+
+/// Forks, runs `f` in the child, and returns the keys it produced.
+fn keys_in_child(f: impl FnOnce() -> [u64; 4]) -> [u64; 4] {
+    let mut fds = [0; 2];
+    unsafe {
+        assert_eq!(0, libc::pipe(fds.as_mut_ptr()));
+        let pid = libc::fork();
+        assert!(pid >= 0);
+        if pid == 0 {
+            let keys = f();
+            libc::write(fds[1], keys.as_ptr() as *const libc::c_void, 32);
+            libc::_exit(0);
+        }
+        let mut keys = [0_u64; 4];
+        assert_eq!(32, libc::read(fds[0], keys.as_mut_ptr() as *mut libc::c_void, 32));
+        let mut status = 0;
+        assert_eq!(pid, libc::waitpid(pid, &mut status, 0));
+        libc::close(fds[0]);
+        libc::close(fds[1]);
+        keys
+    }
+}
+
+// This is a single test so that no other thread creates states between the fork and the parent's `RandomState::new()`.
+#[test]
+fn test_keys_differ_after_fork() {
+    // Only the fixed keys are used before the first fork, which sets up the process wide seeds but not the counter.
+    let _ = RandomState::with_seed(1);
+    let _ = AHasher::default();
+    #[cfg(feature = "fork-safe")]
+    {
+        let child = keys_in_child(|| RandomState::new().to_seeds());
+        assert_ne!(child, RandomState::new().to_seeds());
+    }
+
+    // Make sure the seeds are initialized before forking.
+    let _ = RandomState::new();
+
+    #[cfg(not(feature = "fork-safe"))]
+    {
+        let child = keys_in_child(|| RandomState::new().to_seeds());
+        assert_eq!(child, RandomState::new().to_seeds(), "Without reseeding the child repeats the parent's keys");
+    }
+
+    let child = keys_in_child(|| {
+        ahash::reseed_after_fork().unwrap();
+        RandomState::new().to_seeds()
+    });
+    assert_ne!(child, RandomState::new().to_seeds());
+
+    let child = keys_in_child(|| {
+        ahash::reseed_after_fork().unwrap();
+        [ahash::config().reseeded_after_fork as u64, 0, 0, 0]
+    });
+    assert_eq!(child, [1, 0, 0, 0]);
+
+    // Reseeding again replaces the seeds that were written by the previous call.
+    let child = keys_in_child(|| {
+        ahash::reseed_after_fork().unwrap();
+        let first = RandomState::new().to_seeds();
+        ahash::reseed_after_fork().unwrap();
+        [(first != RandomState::new().to_seeds()) as u64, 0, 0, 0]
+    });
+    assert_eq!(child, [1, 0, 0, 0]);
+
+    // The fixed keys are not changed, so that maps inherited from the parent can still be used.
+    let child = keys_in_child(|| {
+        ahash::reseed_after_fork().unwrap();
+        RandomState::with_seed(1).to_seeds()
+    });
+    assert_eq!(child, RandomState::with_seed(1).to_seeds());
+
+    let mut map: HashMap<u64, u64, BuildHasherDefault<AHasher>> = HashMap::default();
+    map.extend((0..100).map(|i| (i, i * 2)));
+    let child = keys_in_child(|| {
+        ahash::reseed_after_fork().unwrap();
+        map.insert(100, 200);
+        let found = (0..=100).filter(|i| map.get(i) == Some(&(i * 2))).count() as u64;
+        [found, map.len() as u64, 0, 0]
+    });
+    assert_eq!(child, [101, 101, 0, 0]);
+
+    #[cfg(feature = "fork-safe")]
+    {
+        let child = keys_in_child(|| RandomState::new().to_seeds());
+        assert_ne!(child, RandomState::new().to_seeds());
+        let child = keys_in_child(|| RandomState::with_seed(1).to_seeds());
+        assert_eq!(child, RandomState::with_seed(1).to_seeds());
+        let child = keys_in_child(|| {
+            let found = (0..100).filter(|i| map.get(i) == Some(&(i * 2))).count() as u64;
+            [found, 0, 0, 0]
+        });
+        assert_eq!(child, [100, 0, 0, 0]);
+    }
+}
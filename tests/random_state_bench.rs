@@ -0,0 +1,18 @@
+use criterion::*;
+
+// The source can only be set once per process, so this is a separate target from the `new-default` benchmark.
+#[cfg_attr(not(feature = "std"), allow(unused_variables))]
+fn bench_new_thread_local(c: &mut Criterion) {
+    #[cfg(feature = "std")]
+    {
+        use ahash::random_state::{set_random_source, ThreadLocalRandomSource};
+        use ahash::RandomState;
+
+        set_random_source(ThreadLocalRandomSource).expect("Random source was already set");
+        let mut group = c.benchmark_group("random_state");
+        group.bench_function("new-thread_local", |b| b.iter(RandomState::new));
+    }
+}
+
+criterion_main!(benches);
+criterion_group!(benches, bench_new_thread_local);